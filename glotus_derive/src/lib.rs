@@ -0,0 +1,61 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, parse_macro_input, spanned::Spanned};
+
+/// 为结构体实现 `ComponentBundle`，每个字段可以是组件，也可以是另一个 bundle
+///
+/// ```ignore
+/// #[derive(Bundle)]
+/// struct MeshEntityBundle {
+///     transform: Transform,
+///     renderable: Renderable,
+///     script: Scriptable,
+/// }
+/// ```
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_bundle(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_bundle(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Bundle can only be derived for structs",
+        ));
+    };
+
+    let fields: Vec<TokenStream2> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| {
+                let ident = f.ident.as_ref().unwrap();
+                quote! { self.#ident }
+            })
+            .collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|i| {
+                let index = Index::from(i);
+                quote! { self.#index }
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::glotus::ComponentBundle for #name #ty_generics #where_clause {
+            fn add_to_entity(self, world: &::glotus::World, entity: ::glotus::EntityHandle) {
+                #( ::glotus::ComponentBundle::add_to_entity(#fields, world, entity); )*
+            }
+        }
+    })
+}
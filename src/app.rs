@@ -1,25 +1,46 @@
+mod app_builder;
 mod app_config;
-
-pub use app_config::AppConfig;
+mod config_loader;
+mod frame_capture;
+mod log_config;
+mod plugin;
+mod window_controller;
+
+pub use app_builder::AppBuilder;
+pub use app_config::{AppConfig, FixedStepOverrun, HeadlessMode};
+pub use config_loader::{CONFIG_ENV_PREFIX, ConfigError};
+pub use frame_capture::FrameCapture;
+pub use log_config::*;
+pub use plugin::*;
+
+use app_builder::Startup;
+use window_controller::WindowController;
 
 use crate::{
-    AppContext, AppEvent, Resolution, SystemDispatcher,
-    utils::{self},
-};
-use glfw::{
-    Action, Context, Glfw, GlfwReceiver, Key, PWindow, SwapInterval, WindowEvent, ffi::glfwGetTime,
+    AppContext, AppEvent, AppEventQueue, AppExit, FixedTime, IClock, InputState, Profiler,
+    Resolution, SystemClock, SystemDescriptor, SystemDispatcher, Time, WindowState,
 };
-use log::{error, info};
+use glfw::{Action, Context, ContextCreationApi, Glfw, GlfwReceiver, Key, PWindow, WindowEvent};
+use log::{debug, error, info};
 use std::{cell::RefCell, error::Error, rc::Rc, time::Duration};
 
 pub struct App {
     is_running: bool,
+    // 没有窗口时记录的退出请求
+    close_requested: bool,
+    is_shut_down: bool,
     window: Option<Rc<RefCell<PWindow>>>,
     glfw: Option<Rc<RefCell<Glfw>>>,
     event_receiver: Option<Rc<RefCell<GlfwReceiver<(f64, WindowEvent)>>>>,
+    window_controller: WindowController,
 
     context: Rc<RefCell<AppContext>>,
     system_dispatcher: Rc<RefCell<SystemDispatcher>>,
+
+    // 与 Profiler 共享，保证性能记录和帧使用同一个时间基准
+    clock: Rc<dyn IClock>,
+    // 还没有被 fixed_update 消耗的时间
+    fixed_accumulator: f64,
 }
 
 // main
@@ -29,18 +50,40 @@ impl App {
     }
 
     pub fn new_with_config(config: AppConfig) -> Rc<RefCell<Self>> {
+        Self::builder_with_config(config).build()
+    }
+
+    /// 包含默认插件的构建器
+    pub fn builder() -> AppBuilder {
+        Self::builder_with_config(Default::default())
+    }
+
+    pub fn builder_with_config(config: AppConfig) -> AppBuilder {
+        AppBuilder::new(config).with_plugin(DefaultPlugins)
+    }
+
+    pub(crate) fn from_parts(
+        context: Rc<RefCell<AppContext>>,
+        system_dispatcher: SystemDispatcher,
+        startups: Vec<Startup>,
+    ) -> Rc<RefCell<Self>> {
         let app = Self {
             is_running: false,
+            close_requested: false,
+            is_shut_down: false,
             window: None,
             glfw: None,
             event_receiver: None,
-            context: Rc::new(RefCell::new(AppContext::new(config))),
-            system_dispatcher: Rc::new(RefCell::new(SystemDispatcher::new_with_default_systems())),
+            window_controller: WindowController::default(),
+            context,
+            system_dispatcher: Rc::new(RefCell::new(system_dispatcher)),
+            clock: Rc::new(SystemClock::new()),
+            fixed_accumulator: 0.0,
         };
+        app.share_clock();
 
-        utils::setup_logger();
         let app_rc = Rc::new(RefCell::new(app));
-        app_rc.borrow_mut().init();
+        app_rc.borrow_mut().init(startups);
         app_rc
     }
 
@@ -50,57 +93,48 @@ impl App {
     {
         let result = action(self.context.clone());
 
+        // 执行 build 过程中记录的命令
+        self.context.borrow().world.borrow_mut().apply_commands();
+
         result
     }
 
+    /// 注册系统，可通过 `SystemDescriptor` 指定阶段、顺序和运行条件
+    pub fn add_system(&self, system: impl Into<SystemDescriptor>) {
+        self.system_dispatcher.borrow_mut().add_system(system);
+    }
+
     pub fn run(&mut self) {
+        // 初始化阶段已请求退出
+        if self.should_close() {
+            return;
+        }
         self.is_running = true;
 
         info!("app starts to running...");
 
-        let fixed_dt: f32;
-        let target_render_dt: Option<f32>;
-        {
+        let target_render_dt: Option<f64> = {
             let context = self.context.borrow();
             let config = context.app_config.borrow();
-            fixed_dt = 1.0 / config.fixed_update_fps as f32;
-            target_render_dt = config.target_render_fps.map(|fps| 1.0 / fps as f32);
-        }
+            config.target_render_fps.map(|fps| 1.0 / fps as f64)
+        };
 
-        let last_time = self.get_current_time();
-        let mut last_render_update_time = last_time;
-        let mut last_fixed_update_time = last_time;
+        let mut last_render_update_time = self.get_current_time();
 
         // FPS 统计
         let mut frame_count = 0;
-        let mut fps_timer = last_time;
+        let mut fps_timer = last_render_update_time;
 
         while self.is_running {
-            // 计算事件
-            let now = self.get_current_time();
-
-            // glfw事件
-            self.glfw.as_ref().unwrap().borrow_mut().poll_events();
-            self.handle_window_event();
-
-            //处理事件队列
-            self.handle_event_queue();
-
-            // FixedUpdate 循环
-            while now - last_fixed_update_time >= fixed_dt {
-                self.fixed_update(fixed_dt);
-                last_fixed_update_time += fixed_dt
-            }
-
             // Render 限帧
             if let Some(render_dt) = target_render_dt {
-                let since_last_render = now - last_render_update_time;
+                let since_last_render = self.get_current_time() - last_render_update_time;
                 let remaining = render_dt - since_last_render;
 
                 if remaining > 0.0 {
                     // 如果剩余时间 > 4ms，睡眠 50% 的时间
                     if remaining > 0.005 {
-                        std::thread::sleep(Duration::from_secs_f32(remaining * 0.9));
+                        std::thread::sleep(Duration::from_secs_f64(remaining * 0.9));
                     }
                     // 最后自旋等待，确保精确时间
                     loop {
@@ -115,19 +149,21 @@ impl App {
                 }
             }
 
-            // 渲染
-            let delta_dt = self.get_current_time() - last_render_update_time;
+            let now = self.get_current_time();
+            let delta_dt = now - last_render_update_time;
             //  更新记录的时间
-            last_render_update_time = self.get_current_time();
+            last_render_update_time = now;
 
-            self.render_update(delta_dt);
+            self.step(delta_dt);
 
-            self.window.as_ref().unwrap().borrow_mut().swap_buffers();
+            if self.should_close() {
+                self.is_running = false;
+            }
 
             // FPS 统计
             frame_count += 1;
             if last_render_update_time - fps_timer >= 1.0 {
-                let actual_fps = frame_count as f32 / (last_render_update_time - fps_timer);
+                let actual_fps = frame_count as f64 / (last_render_update_time - fps_timer);
                 info!(
                     "FPS: {:.1} | Frame Time: {:.3}ms",
                     actual_fps,
@@ -136,20 +172,117 @@ impl App {
                 frame_count = 0;
                 fps_timer = last_render_update_time;
             }
+        }
+
+        info!("app is going to close...");
+        self.shutdown();
+    }
 
-            // 清空事件队列
-            {
-                self.context.borrow().event_queue.borrow_mut().clear();
+    /// 推进一帧：处理窗口事件，按固定步长运行 fixed_update，再运行 update 和渲染
+    ///
+    /// 时间只由 `dt` 决定，与真实时间无关，可以在测试中确定地推进。
+    pub fn step(&mut self, dt: f64) {
+        self.advance_frame(dt, false);
+    }
+
+    /// 推进一帧并在交换缓冲前读取屏幕画面，没有 OpenGL 上下文时返回 None
+    pub fn step_and_capture(&mut self, dt: f64) -> Option<FrameCapture> {
+        self.advance_frame(dt, true)
+    }
+
+    /// 每帧推进 fixed_dt 的真实时间，共推进 n 帧，请求退出后提前结束
+    ///
+    /// 时间缩放为 1 且没有暂停时每帧正好运行一次 fixed_update；
+    /// 否则 fixed_update 按缩放后的时间运行，暂停期间不运行，update 仍然每帧运行一次。
+    pub fn run_frames(&mut self, n: usize) {
+        let fixed_dt = self.fixed_dt();
+        for _ in 0..n {
+            if self.should_close() {
+                break;
             }
+            self.step(fixed_dt);
         }
+    }
 
-        info!("app is going to close...");
+    /// 替换 `run` 和 Profiler 使用的时钟，默认为 `SystemClock`
+    pub fn set_clock<C: IClock + 'static>(&mut self, clock: C) {
+        self.clock = Rc::new(clock);
+        self.share_clock();
+    }
+
+    /// 发送 `AppExit`，然后依次关闭系统、删除所有实体、释放资产，只执行一次
+    ///
+    /// `run` 结束和 App 被释放时自动调用，OpenGL 上下文在此之后才销毁。
+    pub fn shutdown(&mut self) {
+        if self.is_shut_down {
+            return;
+        }
+        self.is_shut_down = true;
+        self.close();
+        info!("app is shutting down...");
+
+        self.context.borrow().world.borrow().send_event(AppExit);
+        self.system_dispatcher
+            .borrow_mut()
+            .shutdown_systems(self.context.clone());
+
+        let context = self.context.borrow();
+        context.world.borrow_mut().despawn_all();
+        context.asset_manager.borrow().clear();
+    }
+
+    /// 是否已请求退出
+    pub fn should_close(&self) -> bool {
+        self.close_requested
+            || self
+                .window
+                .as_ref()
+                .is_some_and(|window| window.borrow().should_close())
+    }
+}
+
+impl Drop for App {
+    fn drop(&mut self) {
+        // 避免在 panic 展开过程中再次 panic
+        if !std::thread::panicking() {
+            self.shutdown();
+        }
     }
 }
 
 // utils
 impl App {
-    fn init(&mut self) {
+    fn init(&mut self, startups: Vec<Startup>) {
+        let headless = self.context.borrow().app_config.borrow().headless;
+        if headless != HeadlessMode::NoGl {
+            self.init_window(headless);
+        }
+        self.apply_window_commands();
+
+        // 插件的启动任务，例如加载默认资产
+        for startup in startups {
+            let result = startup(self.context.clone());
+            self.context.borrow().world.borrow_mut().apply_commands();
+            if let Err(e) = result {
+                error!("plugin startup error: {}", e);
+                self.close();
+                return;
+            }
+        }
+
+        // 初始化system
+        let result = self
+            .system_dispatcher
+            .borrow_mut()
+            .init_systems(self.context.clone());
+        if let Err(e) = result {
+            // 单个系统的错误已由 SystemErrorPolicy 处理，到这里的都需要退出
+            error!("init system error: {}", e);
+            self.close();
+        };
+    }
+
+    fn init_window(&mut self, headless: HeadlessMode) {
         let context = self.context.borrow();
         let config = context.app_config.borrow();
         let width = config.resolution.width;
@@ -164,6 +297,15 @@ impl App {
         ));
         // 设置采样
         glfw.window_hint(glfw::WindowHint::Samples(config.anti_pixel.to_num()));
+        // 无界面模式下隐藏窗口
+        if headless != HeadlessMode::Disabled {
+            glfw.window_hint(glfw::WindowHint::Visible(false));
+        }
+        if headless == HeadlessMode::OsMesa {
+            glfw.window_hint(glfw::WindowHint::ContextCreationApi(
+                ContextCreationApi::OsMesa,
+            ));
+        }
 
         // 创建窗口
         let (mut window, events) = glfw
@@ -183,13 +325,14 @@ impl App {
         window.set_cursor_pos_polling(true); // 监听鼠标移动事件
         window.set_framebuffer_size_polling(true); // 监听窗口大小变化
         window.set_close_polling(true);
+        window.set_pos_polling(true);
+        window.set_size_polling(true);
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
+        window.set_maximize_polling(true);
 
         // 开启垂直同步
-        if config.v_sync {
-            glfw.set_swap_interval(SwapInterval::Sync(1));
-        } else {
-            glfw.set_swap_interval(SwapInterval::None);
-        }
+        window_controller::set_swap_interval(&mut glfw, config.v_sync);
 
         // 加载 OpenGL 函数指针
         gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
@@ -208,9 +351,22 @@ impl App {
             info!("支持的 GLSL 版本: {:?}", version_str);
         }
 
-        // 启用原始鼠标输入（避免系统加速影响）
-        window.set_cursor_mode(glfw::CursorMode::Disabled);
-        window.set_cursor_pos(width as f64 / 2.0, height as f64 / 2.0); // 初始居中
+        // 光标初始居中
+        if headless == HeadlessMode::Disabled {
+            window.set_cursor_pos(width as f64 / 2.0, height as f64 / 2.0);
+        }
+
+        // 记录窗口和显示器信息，初始的窗口模式和光标模式在第一帧前应用
+        {
+            let world = context.world.borrow();
+            let mut window_state = world.resource_mut::<WindowState>();
+            let (x, y) = window.get_pos();
+            let (window_width, window_height) = window.get_size();
+            window_state.set_position_state(x, y);
+            window_state.set_size_state(window_width as u32, window_height as u32);
+            window_state.set_focused(window.is_focused());
+            window_state.set_monitors(window_controller::read_monitors(&mut glfw));
+        }
 
         // 初始化成员
         self.glfw = Some(Rc::new(RefCell::new(glfw)));
@@ -232,15 +388,6 @@ impl App {
         unsafe {
             gl::Viewport(0, 0, width as i32, height as i32);
         }
-
-        // 初始化system
-        if let Err(e) = self
-            .system_dispatcher
-            .borrow_mut()
-            .init_systems(self.context.clone())
-        {
-            error!("init system error: {}", e);
-        };
     }
 
     fn handle_event_queue(&mut self) {
@@ -248,15 +395,20 @@ impl App {
 
         {
             let context = self.context.borrow();
-            let event_queue = context.event_queue.borrow();
+            let world = context.world.borrow();
+            let event_queue = world.resource::<AppEventQueue>();
             let events = event_queue.all_events();
-            let mut input_state = context.input_state.borrow_mut();
+            let mut input_state = world.resource_mut::<InputState>();
 
             for event in events {
                 match event {
-                    AppEvent::Resize { width, height } => unsafe {
-                        gl::Viewport(0, 0, *width, *height);
-                    },
+                    AppEvent::Resize { width, height } => {
+                        if self.window.is_some() {
+                            unsafe {
+                                gl::Viewport(0, 0, *width, *height);
+                            }
+                        }
+                    }
                     AppEvent::Key { key, action } => match action {
                         Action::Press => {
                             input_state.press_key(*key);
@@ -275,6 +427,12 @@ impl App {
                     AppEvent::CursorPos { x, y } => {
                         input_state.set_cursor_delta(*x as f32, *y as f32);
                     }
+                    AppEvent::Focus { focused } => {
+                        if !focused {
+                            input_state.release_all();
+                        }
+                    }
+                    AppEvent::Iconify { .. } => {}
                     AppEvent::MouseButton { button, action } => match action {
                         Action::Press => {
                             input_state.press_mouse_button(*button);
@@ -295,62 +453,203 @@ impl App {
 
     fn fixed_update(&mut self, fixed_dt: f32) {
         // esc 自动退出app
-        if self
-            .context
-            .borrow()
-            .input_state
-            .borrow()
-            .is_key_down(Key::Escape)
-        {
-            self.window
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
-                .set_should_close(true);
-            self.is_running = false;
+        let escape_down = {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource::<InputState>().is_key_down(Key::Escape)
+        };
+        if escape_down {
+            self.close();
         }
 
-        if let Err(e) = self
+        let result = self
             .system_dispatcher
             .borrow_mut()
-            .fixed_run_systems(self.context.clone(), fixed_dt)
-        {
+            .fixed_run_systems(self.context.clone(), fixed_dt);
+        if let Err(e) = result {
             error!("fixed run system error: {}", e);
+            self.close();
         };
 
-        self.context.borrow().input_state.borrow_mut().clear_delta();
+        {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource_mut::<InputState>().clear_delta();
+        }
     }
 
-    fn get_current_time(&self) -> f32 {
-        unsafe {
-            let current_time = glfwGetTime() as f32;
-            current_time
+    fn get_current_time(&self) -> f64 {
+        self.clock.now()
+    }
+
+    fn share_clock(&self) {
+        let context = self.context.borrow();
+        let world = context.world.borrow();
+        world
+            .resource_mut::<Profiler>()
+            .set_clock(self.clock.clone());
+    }
+
+    fn fixed_dt(&self) -> f64 {
+        let context = self.context.borrow();
+        let world = context.world.borrow();
+        world.resource::<FixedTime>().delta_f64()
+    }
+
+    fn advance_frame(&mut self, dt: f64, capture: bool) -> Option<FrameCapture> {
+        {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource_mut::<Profiler>().begin_frame();
+        }
+
+        // glfw事件
+        if let Some(glfw) = &self.glfw {
+            glfw.borrow_mut().poll_events();
+            self.handle_window_event();
+        }
+
+        //处理事件队列
+        self.handle_event_queue();
+
+        // 更新帧时间，暂停时缩放后的时间为 0
+        let scaled_dt = {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource_mut::<Time>().advance(dt)
+        };
+
+        // FixedUpdate 循环
+        let fixed_dt = self.fixed_dt();
+        let (max_steps, max_accumulated_time, overrun) = {
+            let context = self.context.borrow();
+            let config = context.app_config.borrow();
+            (
+                config.max_fixed_steps_per_frame,
+                // 至少能运行一步
+                config.max_fixed_accumulated_time.max(fixed_dt),
+                config.fixed_step_overrun,
+            )
+        };
+
+        // 长时间卡顿后只追赶有限的时间，避免越追越慢
+        let mut dropped_time = 0.0;
+        self.fixed_accumulator += scaled_dt;
+        if self.fixed_accumulator > max_accumulated_time {
+            dropped_time += self.fixed_accumulator - max_accumulated_time;
+            self.fixed_accumulator = max_accumulated_time;
+        }
+
+        let mut steps = 0;
+        while self.fixed_accumulator >= fixed_dt && steps < max_steps {
+            self.fixed_update(fixed_dt as f32);
+            self.fixed_accumulator -= fixed_dt;
+            steps += 1;
         }
+
+        if self.fixed_accumulator >= fixed_dt && overrun == FixedStepOverrun::Drop {
+            let remainder = self.fixed_accumulator % fixed_dt;
+            dropped_time += self.fixed_accumulator - remainder;
+            self.fixed_accumulator = remainder;
+        }
+        if dropped_time > 0.0 {
+            debug!(
+                "fixed update fell behind, {} steps ran, {:.3}s dropped",
+                steps, dropped_time
+            );
+        }
+
+        // 渲染插值的比例
+        {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            let mut fixed_time = world.resource_mut::<FixedTime>();
+            fixed_time.record_frame(steps, dropped_time);
+            fixed_time.set_alpha((self.fixed_accumulator / fixed_dt) as f32);
+
+            let mut profiler = world.resource_mut::<Profiler>();
+            profiler.set_counter("fixed_steps", steps as f64);
+            profiler.set_counter("entities", world.entity_count() as f64);
+        }
+
+        // 渲染
+        self.render_update(scaled_dt as f32);
+
+        let captured = if capture { self.capture_screen() } else { None };
+
+        if let Some(window) = &self.window {
+            window.borrow_mut().swap_buffers();
+        }
+
+        // 清空事件队列
+        {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource_mut::<AppEventQueue>().clear();
+        }
+
+        self.apply_window_commands();
+
+        {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            world.resource_mut::<Profiler>().end_frame();
+        }
+
+        captured
+    }
+
+    /// 应用本帧对 `WindowState` 的修改
+    fn apply_window_commands(&mut self) {
+        let context = self.context.borrow();
+        let world = context.world.borrow();
+        let mut window_state = world.resource_mut::<WindowState>();
+        let mut glfw = self.glfw.as_ref().map(|glfw| glfw.borrow_mut());
+        let mut window = self.window.as_ref().map(|window| window.borrow_mut());
+
+        let cursor_mode = window_state.cursor_mode();
+        self.window_controller.apply(
+            glfw.as_deref_mut(),
+            window.as_deref_mut(),
+            &mut window_state,
+        );
+        if window_state.cursor_mode() != cursor_mode {
+            world.resource_mut::<InputState>().reset_cursor();
+        }
+    }
+
+    fn capture_screen(&self) -> Option<FrameCapture> {
+        self.window.as_ref()?;
+        let context = self.context.borrow();
+        let world = context.world.borrow();
+        let resolution = world.resource::<WindowState>().get_resolution();
+        Some(FrameCapture::read_screen(resolution))
     }
 
     fn render_update(&mut self, delta_dt: f32) {
-        if let Err(e) = self
+        let result = self
             .system_dispatcher
             .borrow_mut()
-            .run_systems(self.context.clone(), delta_dt)
-        {
+            .run_systems(self.context.clone(), delta_dt);
+        if let Err(e) = result {
             error!("render update system error: {}", e);
+            self.close();
         };
     }
 
     fn close(&mut self) {
-        self.window
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .set_should_close(true);
+        if let Some(window) = &self.window {
+            window.borrow_mut().set_should_close(true);
+        }
+        self.close_requested = true;
         self.is_running = false;
     }
 
     fn handle_window_event(&mut self) {
         let context = self.context.borrow();
-        let mut event_queue = context.event_queue.borrow_mut();
-        let mut window_state = context.window_state.borrow_mut();
+        let world = context.world.borrow();
+        let mut event_queue = world.resource_mut::<AppEventQueue>();
+        let mut window_state = world.resource_mut::<WindowState>();
         for (_, event) in
             glfw::flush_messages(&mut *(self.event_receiver.as_ref().unwrap().borrow_mut()))
         {
@@ -377,8 +676,130 @@ impl App {
                     window_state.set_resolution(Resolution::new(width as u32, height as u32));
                     event_queue.push(AppEvent::Resize { width, height });
                 }
+                WindowEvent::Pos(x, y) => {
+                    window_state.set_position_state(x, y);
+                }
+                WindowEvent::Size(width, height) => {
+                    window_state.set_size_state(width as u32, height as u32);
+                }
+                WindowEvent::Focus(focused) => {
+                    window_state.set_focused(focused);
+                    event_queue.push(AppEvent::Focus { focused });
+                }
+                WindowEvent::Iconify(iconified) => {
+                    window_state.set_minimized(iconified);
+                    event_queue.push(AppEvent::Iconify { iconified });
+                }
+                WindowEvent::Maximize(maximized) => {
+                    window_state.set_maximized(maximized);
+                }
                 _ => (),
             };
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EntityHandle, Events, IComponent, ISystem, MaterialHandle, MaterialManager, World,
+    };
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// 关闭时记录顺序以及是否读到 AppExit
+    struct ShutdownProbe {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl ISystem for ShutdownProbe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn shutdown(&mut self, app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+            let context = app_context.borrow();
+            let exit = !context
+                .world
+                .borrow()
+                .resource::<Events<AppExit>>()
+                .is_empty();
+            self.log
+                .borrow_mut()
+                .push(format!("shutdown {} exit={}", self.name, exit));
+            Ok(())
+        }
+    }
+
+    /// 移除时记录引用的材质是否还在
+    struct AssetUser {
+        log: Log,
+        materials: Rc<RefCell<MaterialManager>>,
+        material: MaterialHandle,
+    }
+
+    impl IComponent for AssetUser {
+        fn on_remove(&mut self, _entity: EntityHandle, _world: &World) {
+            let alive = self.materials.borrow().get(self.material).is_some();
+            self.log
+                .borrow_mut()
+                .push(format!("on_remove material={}", alive));
+        }
+    }
+
+    #[test]
+    fn shutdown_tears_down_in_order() {
+        let app = App::new_with_config(AppConfig {
+            headless: HeadlessMode::NoGl,
+            log: LogConfig {
+                install: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let log: Log = Rc::default();
+        app.borrow().add_system(ShutdownProbe {
+            name: "First",
+            log: log.clone(),
+        });
+        app.borrow().add_system(ShutdownProbe {
+            name: "Second",
+            log: log.clone(),
+        });
+        let materials = app
+            .borrow()
+            .context
+            .borrow()
+            .asset_manager
+            .borrow()
+            .material_manager
+            .clone();
+        let material = materials.borrow_mut().create(Default::default()).unwrap();
+        {
+            let app = app.borrow();
+            let context = app.context.borrow();
+            let mut world = context.world.borrow_mut();
+            world.register_component::<AssetUser>();
+            world.spawn_entity_with(AssetUser {
+                log: log.clone(),
+                materials: materials.clone(),
+                material,
+            });
+        }
+        app.borrow_mut().run_frames(1);
+
+        app.borrow_mut().shutdown();
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "shutdown Second exit=true",
+                "shutdown First exit=true",
+                "on_remove material=true",
+            ]
+        );
+        assert!(materials.borrow().get(material).is_none());
+    }
+}
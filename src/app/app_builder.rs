@@ -0,0 +1,142 @@
+use std::{any::TypeId, cell::RefCell, collections::HashSet, error::Error, rc::Rc};
+
+use log::{debug, info, warn};
+
+use crate::{
+    App, AppConfig, AppContext, IComponent, IPlugin, Pass, Pipeline, SystemDescriptor,
+    SystemDispatcher, utils,
+};
+
+pub(crate) type Startup = Box<dyn FnOnce(Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>>>;
+
+/// 组装 App：收集插件，在 `build` 时依次构建插件并创建 App
+///
+/// ```ignore
+/// let app = App::builder()
+///     .without_plugin::<CameraPlugin>()
+///     .with_plugin(MyCameraPlugin)
+///     .build();
+/// ```
+pub struct AppBuilder {
+    context: Rc<RefCell<AppContext>>,
+    dispatcher: SystemDispatcher,
+    plugins: Vec<(TypeId, Box<dyn IPlugin>)>,
+    // 已构建和被禁用的插件都不会再构建
+    built: HashSet<TypeId>,
+    disabled: HashSet<TypeId>,
+    startups: Vec<Startup>,
+}
+
+impl AppBuilder {
+    /// 不含任何插件的构建器
+    pub fn new(config: AppConfig) -> Self {
+        utils::setup_logger(&config.log);
+        info!("effective config:\n{}", config.to_toml());
+        Self {
+            context: Rc::new(RefCell::new(AppContext::new(config))),
+            dispatcher: SystemDispatcher::new(),
+            plugins: Vec::new(),
+            built: HashSet::new(),
+            disabled: HashSet::new(),
+            startups: Vec::new(),
+        }
+    }
+
+    /// 添加插件，插件在 `build` 时按添加顺序构建，同类型插件只构建一次
+    pub fn add_plugin<P: IPlugin>(&mut self, plugin: P) -> &mut Self {
+        self.plugins.push((TypeId::of::<P>(), Box::new(plugin)));
+        self
+    }
+
+    pub fn with_plugin<P: IPlugin>(mut self, plugin: P) -> Self {
+        self.add_plugin(plugin);
+        self
+    }
+
+    /// 禁用插件，包括插件组中的插件
+    pub fn disable_plugin<P: IPlugin>(&mut self) -> &mut Self {
+        self.disabled.insert(TypeId::of::<P>());
+        self
+    }
+
+    pub fn without_plugin<P: IPlugin>(mut self) -> Self {
+        self.disable_plugin::<P>();
+        self
+    }
+
+    /// 读取配置，例如根据无界面模式决定添加哪些系统
+    pub fn with_config<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&AppConfig) -> R,
+    {
+        f(&self.context.borrow().app_config.borrow())
+    }
+
+    /// 注册组件，已注册的组件保持不变
+    pub fn register_component<T: IComponent + 'static>(&mut self) -> &mut Self {
+        self.context
+            .borrow()
+            .world
+            .borrow_mut()
+            .register_component::<T>();
+        self
+    }
+
+    /// 注册系统，可通过 `SystemDescriptor` 指定阶段、顺序和运行条件
+    pub fn add_system(&mut self, system: impl Into<SystemDescriptor>) -> &mut Self {
+        self.dispatcher.add_system(system);
+        self
+    }
+
+    /// 插入资源，同类型的资源会被替换
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> &mut Self {
+        self.context
+            .borrow()
+            .world
+            .borrow_mut()
+            .insert_resource(resource);
+        self
+    }
+
+    /// 向渲染管线插入 Pass
+    pub fn add_pass(&mut self, pass: Pass) -> &mut Self {
+        self.context
+            .borrow()
+            .world
+            .borrow()
+            .resource_mut::<Pipeline>()
+            .insert(pass);
+        self
+    }
+
+    /// 添加启动任务，在 OpenGL 初始化之后、系统初始化之前运行，用于加载资源
+    pub fn add_startup<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> + 'static,
+    {
+        self.startups.push(Box::new(f));
+        self
+    }
+
+    /// 构建所有插件并创建 App
+    pub fn build(mut self) -> Rc<RefCell<App>> {
+        // 插件构建时可能继续添加插件
+        while !self.plugins.is_empty() {
+            let plugins = std::mem::take(&mut self.plugins);
+            for (type_id, plugin) in plugins {
+                if self.disabled.contains(&type_id) {
+                    debug!("plugin {} is disabled", plugin.name());
+                    continue;
+                }
+                if !self.built.insert(type_id) {
+                    warn!("plugin {} is added more than once", plugin.name());
+                    continue;
+                }
+                debug!("build plugin {}", plugin.name());
+                plugin.build(&mut self);
+            }
+        }
+
+        App::from_parts(self.context, self.dispatcher, self.startups)
+    }
+}
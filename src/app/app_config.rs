@@ -1,14 +1,51 @@
-use crate::{AntiPixel, Color, Pipeline, Resolution};
+use crate::{
+    AntiPixel, Color, CursorMode, LogConfig, Pipeline, Resolution, SystemErrorPolicy, WindowMode,
+};
+
+/// 无界面运行的方式，用于自动化测试和 CI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeadlessMode {
+    /// 正常显示窗口
+    #[default]
+    Disabled,
+    /// 创建隐藏的窗口，OpenGL 正常工作，可以读取渲染结果
+    HiddenWindow,
+    /// 使用 OSMesa 创建离屏上下文，可以配合 Mesa llvmpipe 在没有显示服务器的环境中渲染，
+    /// 需要 glfw 编译时启用 OSMesa
+    OsMesa,
+    /// 不创建窗口和 OpenGL 上下文，不运行 RenderSystem
+    NoGl,
+}
+
+/// fixed_update 追不上真实时间时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FixedStepOverrun {
+    /// 丢弃单帧步数上限之外的时间，游戏时间直接跳过卡顿
+    #[default]
+    Drop,
+    /// 保留剩余的时间，在之后的帧里继续追赶，游戏时间暂时变慢
+    Slow,
+}
 
 pub struct AppConfig {
     pub title: String,
     pub target_render_fps: Option<u32>, // None = Unlimited
     pub fixed_update_fps: u32,          // e.g. 60
+    pub max_fixed_steps_per_frame: u32,
+    pub max_fixed_accumulated_time: f64, // 秒，超过的部分总是丢弃
+    pub fixed_step_overrun: FixedStepOverrun,
     pub v_sync: bool,
+    pub window_mode: WindowMode,
+    pub cursor_mode: CursorMode, // 无界面模式下不生效
     pub anti_pixel: AntiPixel,
     pub resolution: Resolution,
     pub bg_color: Color,
     pub instancing: bool,
+    pub headless: HeadlessMode,
+    pub system_error_policy: SystemErrorPolicy,
+    pub profiler: bool,          // 启动时是否开启性能分析
+    pub profiler_history: usize, // 保留的帧数
+    pub log: LogConfig,
     pub pipeline_configurer: Option<Box<dyn Fn(&mut Pipeline)>>,
 }
 
@@ -18,11 +55,21 @@ impl Default for AppConfig {
             title: String::from("Rust GLFW opengl"),
             target_render_fps: None,
             fixed_update_fps: 60,
+            max_fixed_steps_per_frame: 8,
+            max_fixed_accumulated_time: 0.25,
+            fixed_step_overrun: FixedStepOverrun::default(),
             v_sync: true,
+            window_mode: WindowMode::default(),
+            cursor_mode: CursorMode::default(),
             instancing: false,
+            headless: HeadlessMode::default(),
             anti_pixel: AntiPixel::MSAA4,
             resolution: Resolution::new(1440, 960),
             bg_color: Color::from_rgb(50, 75, 75),
+            system_error_policy: SystemErrorPolicy::default(),
+            profiler: false,
+            profiler_history: 300,
+            log: LogConfig::default(),
             pipeline_configurer: None,
         }
     }
@@ -0,0 +1,1016 @@
+use std::{
+    env,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use log::{LevelFilter, warn};
+use serde::Deserialize;
+use thiserror::Error;
+use toml::{Table, Value, de::ValueDeserializer};
+
+use crate::{
+    AntiPixel, AppConfig, Color, CursorMode, FileLogConfig, FixedStepOverrun, HeadlessMode,
+    LogSink, MonitorSelection, Resolution, SystemErrorPolicy, VideoMode, VideoModeSelection,
+    WindowMode,
+};
+
+/// 环境变量前缀，`GLOTUS_ANTI_PIXEL` 对应 `anti_pixel`，`GLOTUS_LOG__LEVEL` 对应 `log.level`
+pub const CONFIG_ENV_PREFIX: &str = "GLOTUS_";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    #[error("Unknown config field `{0}`")]
+    UnknownField(String),
+    #[error("Invalid value {value} for `{field}`, expected {expected}")]
+    InvalidValue {
+        field: String,
+        value: String,
+        expected: String,
+    },
+    #[error("Config field `{field}` can not be set: {reason}")]
+    Unsupported { field: String, reason: String },
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    /// 出错的位置，如 `app.toml:12`、环境变量名或命令行参数
+    #[error("{location}: {source}")]
+    At {
+        location: String,
+        source: Box<ConfigError>,
+    },
+}
+
+impl ConfigError {
+    fn at(self, location: impl Into<String>) -> Self {
+        ConfigError::At {
+            location: location.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+const FIXED_STEP_OVERRUNS: &[(&str, FixedStepOverrun)] = &[
+    ("drop", FixedStepOverrun::Drop),
+    ("slow", FixedStepOverrun::Slow),
+];
+
+const CURSOR_MODES: &[(&str, CursorMode)] = &[
+    ("normal", CursorMode::Normal),
+    ("hidden", CursorMode::Hidden),
+    ("captured", CursorMode::Captured),
+];
+
+const ANTI_PIXELS: &[(&str, AntiPixel)] = &[
+    ("none", AntiPixel::NONE),
+    ("msaa2", AntiPixel::MSAA2),
+    ("msaa4", AntiPixel::MSAA4),
+    ("msaa8", AntiPixel::MSAA8),
+    ("msaa16", AntiPixel::MSAA16),
+    ("msaa32", AntiPixel::MSAA32),
+];
+
+const HEADLESS_MODES: &[(&str, HeadlessMode)] = &[
+    ("disabled", HeadlessMode::Disabled),
+    ("hidden_window", HeadlessMode::HiddenWindow),
+    ("osmesa", HeadlessMode::OsMesa),
+    ("no_gl", HeadlessMode::NoGl),
+];
+
+const LEVELS: &[(&str, LevelFilter)] = &[
+    ("off", LevelFilter::Off),
+    ("error", LevelFilter::Error),
+    ("warn", LevelFilter::Warn),
+    ("info", LevelFilter::Info),
+    ("debug", LevelFilter::Debug),
+    ("trace", LevelFilter::Trace),
+];
+
+/// 从配置文件、环境变量和命令行读取配置
+///
+/// 配置文件为 TOML，键与 `AppConfig` 的字段同名，日志配置位于 `[log]`、
+/// `[log.modules]` 和 `[log.file]` 中，完整的格式可参考 `to_toml` 的输出。
+/// `pipeline_configurer` 和 `LogSink::Memory` 只能在代码中设置。
+impl AppConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        config.apply_file(path)?;
+        Ok(config)
+    }
+
+    pub fn from_toml_str(src: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        config.apply_toml_str(src)?;
+        Ok(config)
+    }
+
+    /// 按顺序合并默认值、配置文件、环境变量和命令行中的 `--set`
+    ///
+    /// 命令行中有 `--config <path>` 时读取该文件，否则读取 `default_path`，
+    /// 默认文件不存在时跳过。其余的命令行参数被忽略。
+    pub fn load(default_path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let args = Args::parse(env::args().skip(1))?;
+        let mut config = Self::default();
+        if args.config_paths.is_empty() {
+            let default_path = default_path.as_ref();
+            if default_path.exists() {
+                config.apply_file(default_path)?;
+            }
+        }
+        for path in &args.config_paths {
+            config.apply_file(path)?;
+        }
+        // 只在这里读取进程的环境变量，跳过名称或值不是 UTF-8 的变量
+        let vars = env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        config.apply_env(CONFIG_ENV_PREFIX, vars)?;
+        config.apply_sets(&args.sets)?;
+        Ok(config)
+    }
+
+    /// 用配置文件中出现的字段覆盖当前配置
+    pub fn apply_file(&mut self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.apply_toml(&src, &path.display().to_string())
+    }
+
+    pub fn apply_toml_str(&mut self, src: &str) -> Result<(), ConfigError> {
+        self.apply_toml(src, "<string>")
+    }
+
+    /// 应用以 `prefix` 开头的环境变量，去掉前缀后转为小写，`__` 表示下一级
+    ///
+    /// `vars` 通常来自 `std::env::vars()`。不对应任何字段的变量只输出警告并跳过，
+    /// 值无效时仍然返回错误。
+    pub fn apply_env<I, K, V>(&mut self, prefix: &str, vars: I) -> Result<(), ConfigError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .filter(|(name, _)| name.starts_with(prefix))
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            let key = name[prefix.len()..].to_lowercase().replace("__", ".");
+            match self.set_field(&key, &value) {
+                Ok(()) => {}
+                Err(ConfigError::UnknownField(field)) => {
+                    warn!(
+                        "ignoring environment variable {}: unknown config field `{}`",
+                        name, field
+                    );
+                }
+                Err(e) => return Err(e.at(format!("environment variable {}", name))),
+            }
+        }
+        Ok(())
+    }
+
+    /// 处理命令行参数中的 `--config <path>` 和 `--set <key>=<value>`，返回其余的参数
+    ///
+    /// 先按顺序读取所有配置文件，再应用 `--set`。
+    pub fn apply_args<I, S>(&mut self, args: I) -> Result<Vec<String>, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args = Args::parse(args)?;
+        for path in &args.config_paths {
+            self.apply_file(path)?;
+        }
+        self.apply_sets(&args.sets)?;
+        Ok(args.rest)
+    }
+
+    /// 设置单个字段，`key` 为点分路径，如 `resolution`、`log.level`
+    ///
+    /// `value` 按 TOML 值解析，无法解析时作为字符串，因此枚举值不需要加引号。
+    pub fn set_field(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let value = value.trim();
+        let value = Value::deserialize(ValueDeserializer::new(value))
+            .unwrap_or_else(|_| Value::String(value.to_string()));
+        self.set_value(key.trim(), &value)
+    }
+
+    /// 输出为配置文件格式，可以被 `from_toml_str` 读回
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let mut line = |key: &str, value: Value| {
+            let _ = writeln!(out, "{} = {}", key, value);
+        };
+
+        line("title", Value::String(self.title.clone()));
+        line(
+            "target_render_fps",
+            match self.target_render_fps {
+                Some(fps) => Value::Integer(fps as i64),
+                None => Value::String(String::from("unlimited")),
+            },
+        );
+        line(
+            "fixed_update_fps",
+            Value::Integer(self.fixed_update_fps as i64),
+        );
+        line(
+            "max_fixed_steps_per_frame",
+            Value::Integer(self.max_fixed_steps_per_frame as i64),
+        );
+        line(
+            "max_fixed_accumulated_time",
+            Value::Float(self.max_fixed_accumulated_time),
+        );
+        line(
+            "fixed_step_overrun",
+            name_value(FIXED_STEP_OVERRUNS, self.fixed_step_overrun),
+        );
+        line("v_sync", Value::Boolean(self.v_sync));
+        line(
+            "window_mode",
+            Value::String(format_window_mode(self.window_mode)),
+        );
+        line("cursor_mode", name_value(CURSOR_MODES, self.cursor_mode));
+        line("anti_pixel", name_value(ANTI_PIXELS, self.anti_pixel));
+        line(
+            "resolution",
+            Value::Array(vec![
+                Value::Integer(self.resolution.width as i64),
+                Value::Integer(self.resolution.height as i64),
+            ]),
+        );
+        let [r, g, b, _] = self.bg_color.to_arr();
+        line(
+            "bg_color",
+            Value::Array(
+                [r, g, b]
+                    .iter()
+                    .map(|c| Value::Integer((c * 255.0).round() as i64))
+                    .collect(),
+            ),
+        );
+        line("instancing", Value::Boolean(self.instancing));
+        line("headless", name_value(HEADLESS_MODES, self.headless));
+        line(
+            "system_error_policy",
+            Value::String(format_error_policy(self.system_error_policy)),
+        );
+        line("profiler", Value::Boolean(self.profiler));
+        line(
+            "profiler_history",
+            Value::Integer(self.profiler_history as i64),
+        );
+
+        let log = &self.log;
+        out.push_str("\n[log]\n");
+        let _ = writeln!(out, "install = {}", log.install);
+        let _ = writeln!(out, "level = {}", name_value(LEVELS, log.level));
+        let sinks: Vec<Value> = log
+            .sinks
+            .iter()
+            .filter_map(|sink| match sink {
+                LogSink::Stdout => Some("stdout"),
+                LogSink::Stderr => Some("stderr"),
+                LogSink::File(_) => Some("file"),
+                LogSink::Memory(_) => None,
+            })
+            .map(|name| Value::String(name.to_string()))
+            .collect();
+        let _ = writeln!(out, "sinks = {}", Value::Array(sinks));
+
+        if !log.module_levels.is_empty() {
+            out.push_str("\n[log.modules]\n");
+            for (module, level) in &log.module_levels {
+                let _ = writeln!(
+                    out,
+                    "{} = {}",
+                    Value::String(module.clone()),
+                    name_value(LEVELS, *level)
+                );
+            }
+        }
+
+        let file = log.sinks.iter().find_map(|sink| match sink {
+            LogSink::File(file) => Some(file),
+            _ => None,
+        });
+        if let Some(file) = file {
+            out.push_str("\n[log.file]\n");
+            let _ = writeln!(
+                out,
+                "directory = {}",
+                Value::String(file.directory.display().to_string())
+            );
+            if let Some(name) = &file.name {
+                let _ = writeln!(out, "name = {}", Value::String(name.clone()));
+            }
+            // 0 表示不限制
+            let _ = writeln!(out, "max_file_size = {}", file.max_file_size.unwrap_or(0));
+            let _ = writeln!(out, "max_files = {}", file.max_files.unwrap_or(0));
+        }
+        out
+    }
+
+    fn apply_toml(&mut self, src: &str, origin: &str) -> Result<(), ConfigError> {
+        let table: Table = src.parse().map_err(|e: toml::de::Error| {
+            // 出错的行号从 1 开始
+            let line = e
+                .span()
+                .map_or(1, |span| src[..span.start].lines().count().max(1));
+            ConfigError::Syntax(e.message().to_string()).at(format!("{}:{}", origin, line))
+        })?;
+        self.apply_table("", &table).map_err(|e| e.at(origin))
+    }
+
+    /// 按文件中的顺序设置表中的字段，子表的键以 `.` 连接
+    fn apply_table(&mut self, prefix: &str, table: &Table) -> Result<(), ConfigError> {
+        for (key, value) in table {
+            let key = format!("{}{}", prefix, key);
+            match value {
+                Value::Table(table) => self.apply_table(&format!("{}.", key), table)?,
+                _ => self.set_value(&key, value)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_sets(&mut self, sets: &[(String, String)]) -> Result<(), ConfigError> {
+        for (key, value) in sets {
+            self.set_field(key, value)
+                .map_err(|e| e.at(format!("argument --set {}={}", key, value)))?;
+        }
+        Ok(())
+    }
+
+    fn set_value(&mut self, key: &str, value: &Value) -> Result<(), ConfigError> {
+        match key {
+            "title" => self.title = expect_string(key, value)?,
+            "target_render_fps" => {
+                self.target_render_fps = match value {
+                    Value::String(s) if s.eq_ignore_ascii_case("unlimited") => None,
+                    Value::Integer(_) => Some(expect_u32(key, value, 1)?),
+                    _ => return Err(invalid(key, value, "a positive integer or \"unlimited\"")),
+                }
+            }
+            "fixed_update_fps" => self.fixed_update_fps = expect_u32(key, value, 1)?,
+            "max_fixed_steps_per_frame" => {
+                self.max_fixed_steps_per_frame = expect_u32(key, value, 1)?
+            }
+            "max_fixed_accumulated_time" => {
+                self.max_fixed_accumulated_time = expect_positive_f64(key, value)?
+            }
+            "fixed_step_overrun" => {
+                self.fixed_step_overrun = expect_name(key, value, FIXED_STEP_OVERRUNS)?
+            }
+            "v_sync" => self.v_sync = expect_bool(key, value)?,
+            "window_mode" => {
+                let s = expect_string(key, value)?;
+                self.window_mode = parse_window_mode(&s).ok_or_else(|| {
+                    invalid(
+                        key,
+                        value,
+                        "\"windowed\", \"borderless[:<monitor>]\" or \
+                         \"fullscreen[:<monitor>[:<width>x<height>@<refresh rate>]]\", \
+                         monitor is \"primary\", \"current\" or an index",
+                    )
+                })?;
+            }
+            "cursor_mode" => self.cursor_mode = expect_name(key, value, CURSOR_MODES)?,
+            "anti_pixel" => {
+                self.anti_pixel = match value {
+                    // 也可以直接写采样数
+                    Value::Integer(0 | 1) => AntiPixel::NONE,
+                    Value::Integer(samples) => ANTI_PIXELS
+                        .iter()
+                        .map(|(_, anti_pixel)| *anti_pixel)
+                        .find(|anti_pixel| anti_pixel.samples() as i64 == *samples)
+                        .ok_or_else(|| invalid(key, value, "0, 2, 4, 8, 16 or 32 samples"))?,
+                    _ => expect_name(key, value, ANTI_PIXELS)?,
+                }
+            }
+            "resolution" => {
+                let (width, height) = expect_size(key, value)?;
+                self.resolution = Resolution::new(width, height);
+            }
+            "bg_color" => self.bg_color = expect_color(key, value)?,
+            "instancing" => self.instancing = expect_bool(key, value)?,
+            "headless" => self.headless = expect_name(key, value, HEADLESS_MODES)?,
+            "system_error_policy" => {
+                let s = expect_string(key, value)?;
+                self.system_error_policy = parse_error_policy(&s).ok_or_else(|| {
+                    invalid(
+                        key,
+                        value,
+                        "\"continue\", \"stop_app\" or \"disable_after:<count>\"",
+                    )
+                })?;
+            }
+            "profiler" => self.profiler = expect_bool(key, value)?,
+            "profiler_history" => self.profiler_history = expect_usize(key, value, 1)?,
+
+            "log.install" => self.log.install = expect_bool(key, value)?,
+            "log.level" => self.log.level = expect_name(key, value, LEVELS)?,
+            "log.sinks" => self.set_log_sinks(key, value)?,
+            "log.file.directory" => {
+                let directory = PathBuf::from(expect_string(key, value)?);
+                self.update_file_sinks(key, |file| file.directory = directory.clone())?;
+            }
+            "log.file.name" => {
+                let name = expect_string(key, value)?;
+                let name = (!name.is_empty()).then_some(name);
+                self.update_file_sinks(key, |file| file.name = name.clone())?;
+            }
+            "log.file.max_file_size" => {
+                let size = expect_u64(key, value)?;
+                self.update_file_sinks(key, |file| {
+                    file.max_file_size = (size > 0).then_some(size)
+                })?;
+            }
+            "log.file.max_files" => {
+                let count = expect_usize(key, value, 0)?;
+                self.update_file_sinks(key, |file| file.max_files = (count > 0).then_some(count))?;
+            }
+            _ => {
+                let Some(module) = key.strip_prefix("log.modules.") else {
+                    return Err(ConfigError::UnknownField(key.to_string()));
+                };
+                let level = expect_name(key, value, LEVELS)?;
+                match self
+                    .log
+                    .module_levels
+                    .iter_mut()
+                    .find(|(name, _)| name == module)
+                {
+                    Some((_, old)) => *old = level,
+                    None => self.log.module_levels.push((module.to_string(), level)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 替换除 `Memory` 以外的输出，已有的文件配置会被保留
+    fn set_log_sinks(&mut self, key: &str, value: &Value) -> Result<(), ConfigError> {
+        let Value::Array(values) = value else {
+            return Err(invalid(
+                key,
+                value,
+                "an array of \"stdout\", \"stderr\" or \"file\"",
+            ));
+        };
+        let file = self
+            .log
+            .sinks
+            .iter()
+            .find_map(|sink| match sink {
+                LogSink::File(file) => Some(file.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let mut sinks: Vec<LogSink> = self
+            .log
+            .sinks
+            .iter()
+            .filter(|sink| matches!(sink, LogSink::Memory(_)))
+            .cloned()
+            .collect();
+        for value in values {
+            let sink = match value {
+                Value::String(s) if s.eq_ignore_ascii_case("stdout") => LogSink::Stdout,
+                Value::String(s) if s.eq_ignore_ascii_case("stderr") => LogSink::Stderr,
+                Value::String(s) if s.eq_ignore_ascii_case("file") => LogSink::File(file.clone()),
+                _ => return Err(invalid(key, value, "\"stdout\", \"stderr\" or \"file\"")),
+            };
+            sinks.push(sink);
+        }
+        self.log.sinks = sinks;
+        Ok(())
+    }
+
+    fn update_file_sinks(
+        &mut self,
+        key: &str,
+        f: impl Fn(&mut FileLogConfig),
+    ) -> Result<(), ConfigError> {
+        let mut found = false;
+        for sink in &mut self.log.sinks {
+            if let LogSink::File(file) = sink {
+                f(file);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(ConfigError::Unsupported {
+                field: key.to_string(),
+                reason: String::from("log.sinks does not contain \"file\""),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 命令行中与配置相关的参数
+struct Args {
+    config_paths: Vec<PathBuf>,
+    sets: Vec<(String, String)>,
+    rest: Vec<String>,
+}
+
+impl Args {
+    fn parse<I, S>(args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = Args {
+            config_paths: Vec::new(),
+            sets: Vec::new(),
+            rest: Vec::new(),
+        };
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            match flag {
+                "--config" => {
+                    let path = inline.or_else(|| args.next()).ok_or_else(|| {
+                        ConfigError::InvalidArgument(String::from("--config requires a path"))
+                    })?;
+                    parsed.config_paths.push(PathBuf::from(path));
+                }
+                "--set" => {
+                    let set = inline.or_else(|| args.next()).unwrap_or_default();
+                    let Some((key, value)) = set.split_once('=') else {
+                        return Err(ConfigError::InvalidArgument(format!(
+                            "--set expects <key>=<value>, got `{}`",
+                            set
+                        )));
+                    };
+                    parsed.sets.push((key.to_string(), value.to_string()));
+                }
+                _ => parsed.rest.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn invalid(field: &str, value: &Value, expected: &str) -> ConfigError {
+    ConfigError::InvalidValue {
+        field: field.to_string(),
+        value: value.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+fn expect_bool(field: &str, value: &Value) -> Result<bool, ConfigError> {
+    match value {
+        Value::Boolean(b) => Ok(*b),
+        _ => Err(invalid(field, value, "true or false")),
+    }
+}
+
+/// 其他标量也按字符串接受，例如 `title = 2048`
+fn expect_string(field: &str, value: &Value) -> Result<String, ConfigError> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Array(_) | Value::Table(_) => Err(invalid(field, value, "a string")),
+        _ => Ok(value.to_string()),
+    }
+}
+
+fn expect_integer(field: &str, value: &Value, min: i64, max: i64) -> Result<i64, ConfigError> {
+    match value {
+        Value::Integer(i) if (min..=max).contains(i) => Ok(*i),
+        _ if max == i64::MAX => Err(invalid(field, value, &format!("an integer >= {}", min))),
+        _ => Err(invalid(
+            field,
+            value,
+            &format!("an integer between {} and {}", min, max),
+        )),
+    }
+}
+
+fn expect_u32(field: &str, value: &Value, min: u32) -> Result<u32, ConfigError> {
+    expect_integer(field, value, min as i64, u32::MAX as i64).map(|i| i as u32)
+}
+
+fn expect_u64(field: &str, value: &Value) -> Result<u64, ConfigError> {
+    expect_integer(field, value, 0, i64::MAX).map(|i| i as u64)
+}
+
+fn expect_usize(field: &str, value: &Value, min: usize) -> Result<usize, ConfigError> {
+    expect_integer(field, value, min as i64, i64::MAX).map(|i| i as usize)
+}
+
+fn expect_positive_f64(field: &str, value: &Value) -> Result<f64, ConfigError> {
+    let x = match value {
+        Value::Float(x) => *x,
+        Value::Integer(i) => *i as f64,
+        _ => f64::NAN,
+    };
+    if x.is_finite() && x > 0.0 {
+        Ok(x)
+    } else {
+        Err(invalid(field, value, "a positive number"))
+    }
+}
+
+/// 不区分大小写地匹配名称
+fn expect_name<T: Copy>(
+    field: &str,
+    value: &Value,
+    options: &[(&str, T)],
+) -> Result<T, ConfigError> {
+    if let Value::String(s) = value
+        && let Some((_, option)) = options
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+    {
+        return Ok(*option);
+    }
+    let names: Vec<String> = options
+        .iter()
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect();
+    Err(invalid(
+        field,
+        value,
+        &format!("one of {}", names.join(", ")),
+    ))
+}
+
+fn name_value<T: PartialEq>(options: &[(&str, T)], value: T) -> Value {
+    let name = options
+        .iter()
+        .find(|(_, option)| *option == value)
+        .map_or("", |(name, _)| name);
+    Value::String(name.to_string())
+}
+
+/// `[width, height]` 或 `"<width>x<height>"`
+fn expect_size(field: &str, value: &Value) -> Result<(u32, u32), ConfigError> {
+    let size = match value {
+        Value::Array(values) => match values.as_slice() {
+            [Value::Integer(width), Value::Integer(height)] => {
+                u32::try_from(*width).ok().zip(u32::try_from(*height).ok())
+            }
+            _ => None,
+        },
+        Value::String(s) => parse_size(s),
+        _ => None,
+    };
+    size.filter(|&(width, height)| width > 0 && height > 0)
+        .ok_or_else(|| invalid(field, value, "[width, height] or \"<width>x<height>\""))
+}
+
+/// `[r, g, b]`，范围 0 到 255，或 `"#rrggbb"`
+fn expect_color(field: &str, value: &Value) -> Result<Color, ConfigError> {
+    let rgb = match value {
+        Value::Array(values) if values.len() == 3 => values
+            .iter()
+            .map(|value| match value {
+                Value::Integer(c) => u8::try_from(*c).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()
+            .map(|rgb| [rgb[0], rgb[1], rgb[2]]),
+        Value::String(s) => s
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(|rgb| [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]),
+        _ => None,
+    };
+    let [r, g, b] =
+        rgb.ok_or_else(|| invalid(field, value, "[r, g, b] between 0 and 255 or \"#rrggbb\""))?;
+    Ok(Color::from_rgb(r as u32, g as u32, b as u32))
+}
+
+fn parse_size(s: &str) -> Option<(u32, u32)> {
+    let (width, height) = s.split_once(['x', 'X'])?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+fn parse_monitor(s: &str) -> Option<MonitorSelection> {
+    match s.to_ascii_lowercase().as_str() {
+        "primary" => Some(MonitorSelection::Primary),
+        "current" => Some(MonitorSelection::Current),
+        index => index.parse().ok().map(MonitorSelection::Index),
+    }
+}
+
+fn format_monitor(monitor: MonitorSelection) -> String {
+    match monitor {
+        MonitorSelection::Primary => String::from("primary"),
+        MonitorSelection::Current => String::from("current"),
+        MonitorSelection::Index(index) => index.to_string(),
+    }
+}
+
+/// `windowed`、`borderless[:<monitor>]`、`fullscreen[:<monitor>[:<width>x<height>@<refresh rate>]]`
+fn parse_window_mode(s: &str) -> Option<WindowMode> {
+    let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+    let monitor = match parts.get(1) {
+        Some(monitor) => parse_monitor(monitor)?,
+        None => MonitorSelection::Primary,
+    };
+    match (parts[0].to_ascii_lowercase().as_str(), parts.len()) {
+        ("windowed", 1) => Some(WindowMode::Windowed),
+        ("borderless", 1 | 2) => Some(WindowMode::Borderless(monitor)),
+        ("fullscreen", 1 | 2) => Some(WindowMode::Fullscreen(monitor, VideoModeSelection::Current)),
+        ("fullscreen", 3) if parts[2].eq_ignore_ascii_case("current") => {
+            Some(WindowMode::Fullscreen(monitor, VideoModeSelection::Current))
+        }
+        ("fullscreen", 3) => {
+            let (size, refresh_rate) = parts[2].split_once('@')?;
+            let (width, height) = parse_size(size)?;
+            let video_mode = VideoMode {
+                width,
+                height,
+                refresh_rate: refresh_rate.trim().parse().ok()?,
+            };
+            Some(WindowMode::Fullscreen(
+                monitor,
+                VideoModeSelection::Specific(video_mode),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn format_window_mode(mode: WindowMode) -> String {
+    match mode {
+        WindowMode::Windowed => String::from("windowed"),
+        WindowMode::Borderless(monitor) => format!("borderless:{}", format_monitor(monitor)),
+        WindowMode::Fullscreen(monitor, VideoModeSelection::Current) => {
+            format!("fullscreen:{}", format_monitor(monitor))
+        }
+        WindowMode::Fullscreen(monitor, VideoModeSelection::Specific(video_mode)) => format!(
+            "fullscreen:{}:{}x{}@{}",
+            format_monitor(monitor),
+            video_mode.width,
+            video_mode.height,
+            video_mode.refresh_rate
+        ),
+    }
+}
+
+/// `continue`、`stop_app` 或 `disable_after:<count>`
+fn parse_error_policy(s: &str) -> Option<SystemErrorPolicy> {
+    let s = s.trim().to_ascii_lowercase();
+    match s.split_once(':') {
+        Some(("disable_after", count)) => count
+            .trim()
+            .parse()
+            .ok()
+            .map(SystemErrorPolicy::DisableAfter),
+        Some(_) => None,
+        None => match s.as_str() {
+            "continue" => Some(SystemErrorPolicy::Continue),
+            "stop_app" => Some(SystemErrorPolicy::StopApp),
+            _ => None,
+        },
+    }
+}
+
+fn format_error_policy(policy: SystemErrorPolicy) -> String {
+    match policy {
+        SystemErrorPolicy::Continue => String::from("continue"),
+        SystemErrorPolicy::DisableAfter(count) => format!("disable_after:{}", count),
+        SystemErrorPolicy::StopApp => String::from("stop_app"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 取出错误的位置和内部错误
+    fn unwrap_at<T>(result: Result<T, ConfigError>) -> (String, ConfigError) {
+        match result {
+            Err(ConfigError::At { location, source }) => (location, *source),
+            Err(other) => panic!("expected a located error, got {:?}", other),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn parses_values_tables_and_comments() {
+        let config = AppConfig::from_toml_str(
+            r##"
+            # 注释
+            title = "a \"quoted\" \\ title\t" # 行尾注释
+            target_render_fps = 1_000
+            max_fixed_accumulated_time = 0.5
+            v_sync = false
+            resolution = [800, 600]
+            bg_color = "#ff8000"
+
+            [log]
+            level = "debug"
+            sinks = ['stdout', "file"]
+
+            [log.modules]
+            "glotus::render" = "trace"
+
+            [log.file]
+            name = 'C:\logs'
+            max_files = 0
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(config.title, "a \"quoted\" \\ title\t");
+        assert_eq!(config.target_render_fps, Some(1000));
+        assert_eq!(config.max_fixed_accumulated_time, 0.5);
+        assert!(!config.v_sync);
+        assert_eq!(config.resolution, Resolution::new(800, 600));
+        assert_eq!(config.bg_color.to_arr()[0], 1.0);
+        assert_eq!(config.log.level, LevelFilter::Debug);
+        assert_eq!(
+            config.log.module_levels,
+            vec![(String::from("glotus::render"), LevelFilter::Trace)]
+        );
+        assert!(matches!(config.log.sinks[0], LogSink::Stdout));
+        let LogSink::File(file) = &config.log.sinks[1] else {
+            panic!("expected a file sink");
+        };
+        assert_eq!(file.name.as_deref(), Some("C:\\logs"));
+        assert_eq!(file.max_files, None);
+    }
+
+    #[test]
+    fn inline_tables_are_flattened() {
+        let config = AppConfig::from_toml_str("log = { level = \"warn\" }").unwrap();
+        assert_eq!(config.log.level, LevelFilter::Warn);
+    }
+
+    #[test]
+    fn syntax_error_reports_line() {
+        let result = AppConfig::from_toml_str("title = \"ok\"\n\nv_sync = \n");
+        let (location, error) = unwrap_at(result);
+        assert_eq!(location, "<string>:3");
+        assert!(matches!(error, ConfigError::Syntax(_)));
+
+        let result = AppConfig::from_toml_str("title = \"unterminated\n");
+        assert_eq!(unwrap_at(result).0, "<string>:1");
+    }
+
+    #[test]
+    fn invalid_and_unknown_fields_are_errors() {
+        let result = AppConfig::from_toml_str("[log]\nlevel = \"loud\"");
+        let (location, error) = unwrap_at(result);
+        assert_eq!(location, "<string>");
+        assert!(matches!(error, ConfigError::InvalidValue { field, .. } if field == "log.level"));
+
+        let result = AppConfig::from_toml_str("v_sync = 1");
+        assert!(matches!(
+            unwrap_at(result).1,
+            ConfigError::InvalidValue { .. }
+        ));
+
+        let result = AppConfig::from_toml_str("vsync = true");
+        assert!(
+            matches!(unwrap_at(result).1, ConfigError::UnknownField(field) if field == "vsync")
+        );
+    }
+
+    #[test]
+    fn set_field_accepts_bare_names() {
+        let mut config = AppConfig::default();
+        config.set_field("headless", "no_gl").unwrap();
+        config.set_field("resolution", "640x480").unwrap();
+        config.set_field("log.level", " error ").unwrap();
+        config
+            .set_field("system_error_policy", "disable_after:3")
+            .unwrap();
+        assert_eq!(config.headless, HeadlessMode::NoGl);
+        assert_eq!(config.resolution, Resolution::new(640, 480));
+        assert_eq!(config.log.level, LevelFilter::Error);
+        assert_eq!(
+            config.system_error_policy,
+            SystemErrorPolicy::DisableAfter(3)
+        );
+    }
+
+    #[test]
+    fn file_then_env_then_args() {
+        let path = env::temp_dir().join(format!(
+            "glotus_config_precedence_{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "title = \"file\"\nfixed_update_fps = 30\nprofiler_history = 10\n",
+        )
+        .unwrap();
+
+        let mut config = AppConfig::default();
+        let rest = config
+            .apply_args(["--config", path.to_str().unwrap(), "--keep"])
+            .unwrap();
+        assert_eq!(rest, vec![String::from("--keep")]);
+        assert_eq!(config.title, "file");
+
+        config
+            .apply_env(
+                CONFIG_ENV_PREFIX,
+                [
+                    ("GLOTUS_TITLE", "env"),
+                    ("GLOTUS_FIXED_UPDATE_FPS", "45"),
+                    ("OTHER_TITLE", "ignored"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(config.title, "env");
+        assert_eq!(config.fixed_update_fps, 45);
+
+        config.apply_args(["--set", "title=cli"]).unwrap();
+        assert_eq!(config.title, "cli");
+        assert_eq!(config.fixed_update_fps, 45);
+        assert_eq!(config.profiler_history, 10);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn unknown_env_var_is_skipped() {
+        let mut config = AppConfig::default();
+        config
+            .apply_env(
+                CONFIG_ENV_PREFIX,
+                [("GLOTUS_NOT_A_FIELD", "1"), ("GLOTUS_V_SYNC", "false")],
+            )
+            .unwrap();
+        assert!(!config.v_sync);
+    }
+
+    #[test]
+    fn invalid_env_value_is_an_error() {
+        let result =
+            AppConfig::default().apply_env(CONFIG_ENV_PREFIX, [("GLOTUS_V_SYNC", "maybe")]);
+        let (location, _) = unwrap_at(result);
+        assert_eq!(location, "environment variable GLOTUS_V_SYNC");
+    }
+
+    #[test]
+    fn to_toml_round_trip() {
+        let mut config = AppConfig {
+            title: String::from("quote \" and \\ backslash"),
+            target_render_fps: Some(144),
+            max_fixed_accumulated_time: 1.0,
+            window_mode: WindowMode::Fullscreen(
+                MonitorSelection::Index(1),
+                VideoModeSelection::Specific(VideoMode {
+                    width: 1920,
+                    height: 1080,
+                    refresh_rate: 60,
+                }),
+            ),
+            anti_pixel: AntiPixel::MSAA8,
+            headless: HeadlessMode::HiddenWindow,
+            system_error_policy: SystemErrorPolicy::DisableAfter(5),
+            ..Default::default()
+        };
+        config.log.level = LevelFilter::Trace;
+        config
+            .log
+            .module_levels
+            .push((String::from("glotus::context"), LevelFilter::Off));
+        config.log.sinks.insert(0, LogSink::Stderr);
+        if let LogSink::File(file) = &mut config.log.sinks[1] {
+            file.name = Some(String::from("game"));
+            file.max_file_size = None;
+        }
+
+        let src = config.to_toml();
+        let parsed = AppConfig::from_toml_str(&src).unwrap();
+        assert_eq!(parsed.to_toml(), src);
+        assert_eq!(parsed.title, config.title);
+        assert_eq!(parsed.target_render_fps, Some(144));
+        assert_eq!(parsed.max_fixed_accumulated_time, 1.0);
+        assert_eq!(parsed.window_mode, config.window_mode);
+        assert_eq!(parsed.anti_pixel, AntiPixel::MSAA8);
+        assert_eq!(parsed.headless, HeadlessMode::HiddenWindow);
+        assert_eq!(parsed.system_error_policy, config.system_error_policy);
+        assert_eq!(parsed.log.module_levels, config.log.module_levels);
+        assert_eq!(parsed.log.sinks.len(), 2);
+    }
+}
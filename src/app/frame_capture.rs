@@ -0,0 +1,68 @@
+use crate::Resolution;
+
+/// 从屏幕读取的一帧画面，RGBA8 格式，第一行是画面顶部
+pub struct FrameCapture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl FrameCapture {
+    /// 读取默认帧缓冲的后缓冲，需要在交换缓冲前调用
+    pub(crate) fn read_screen(resolution: Resolution) -> Self {
+        let width = resolution.width;
+        let height = resolution.height;
+        let row_size = width as usize * 4;
+        let mut pixels = vec![0u8; row_size * height as usize];
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // OpenGL 的第一行在画面底部，翻转为从上到下
+        let flipped = pixels
+            .chunks_exact(row_size)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels: flipped,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 所有像素，每个像素 4 个字节
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// 读取一个像素，坐标从左上角开始
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[index..index + 4].try_into().ok()
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter};
+
+/// 日志配置
+pub struct LogConfig {
+    /// 为 false 时不安装 logger，由宿主程序自行提供
+    pub install: bool,
+    pub level: LevelFilter,
+    /// 按模块路径前缀设置的级别，最长的前缀优先，如 `("glotus::context", Debug)`
+    pub module_levels: Vec<(String, LevelFilter)>,
+    pub sinks: Vec<LogSink>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            install: true,
+            level: LevelFilter::Info,
+            module_levels: Vec::new(),
+            sinks: vec![LogSink::File(FileLogConfig::default())],
+        }
+    }
+}
+
+/// 日志输出目标
+#[derive(Clone)]
+pub enum LogSink {
+    Stdout,
+    Stderr,
+    File(FileLogConfig),
+    /// 保存在内存中，可用于游戏内控制台和测试
+    Memory(LogBuffer),
+}
+
+/// 日志文件配置
+///
+/// 文件名为 `{启动时间}_{name}.log`，轮换时生成新的文件。
+#[derive(Debug, Clone)]
+pub struct FileLogConfig {
+    pub directory: PathBuf,
+    /// None 时使用可执行文件名
+    pub name: Option<String>,
+    /// 单个文件的最大字节数，超过后轮换到新文件，None 时不轮换
+    pub max_file_size: Option<u64>,
+    /// 目录中同名日志最多保留的文件数，None 时全部保留
+    pub max_files: Option<usize>,
+}
+
+impl Default for FileLogConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("logs"),
+            name: None,
+            max_file_size: Some(10 * 1024 * 1024),
+            max_files: Some(20),
+        }
+    }
+}
+
+/// 一条日志
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// 日志环形缓冲区，写满后丢弃最旧的记录
+///
+/// 克隆共享同一个缓冲区，放入 `LogSink::Memory` 后保留一份用于读取。
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// 当前所有记录的副本，从旧到新
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// 取出并清空所有记录
+    pub fn drain(&self) -> Vec<LogEntry> {
+        self.lock().drain(..).collect()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn push(&self, entry: LogEntry) {
+        let mut entries = self.lock();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    // 写日志的线程 panic 不影响读取
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<LogEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
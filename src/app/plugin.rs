@@ -0,0 +1,92 @@
+use crate::{
+    AppBuilder, Camera, CameraSystem, HeadlessMode, Light, RenderSystem, Renderable, ScriptSystem,
+    Scriptable, Stage, SystemDescriptor, TimerSystem, Timers, TransformPropagateSystem,
+    TransformSystem,
+};
+
+/// 插件：把组件、系统、资源、渲染 Pass 和默认资产打包，在构建 App 时一次性注册
+pub trait IPlugin: 'static {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn build(&self, app: &mut AppBuilder);
+}
+
+/// 内置插件组，可通过 `AppBuilder::disable_plugin` 单独关闭其中的插件
+pub struct DefaultPlugins;
+
+impl IPlugin for DefaultPlugins {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(TransformPlugin)
+            .add_plugin(TimerPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(ScriptPlugin)
+            .add_plugin(RenderPlugin);
+    }
+}
+
+/// 计算 GlobalTransform 并在 fixed_update 之间插值
+///
+/// 世界矩阵在 PreUpdate 中先传播一次，相机、脚本等 Update 阶段的系统读到的是本帧的结果；
+/// PostUpdate 中再传播一次，包含 Update 阶段的修改，供渲染使用。
+pub struct TransformPlugin;
+
+impl IPlugin for TransformPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(
+            SystemDescriptor::new(TransformPropagateSystem)
+                .in_stage(Stage::PreUpdate)
+                .after("TimerSystem"),
+        )
+        .add_system(SystemDescriptor::new(TransformSystem::default()).in_stage(Stage::PostUpdate));
+    }
+}
+
+/// `Timers` 资源和推进它的系统
+pub struct TimerPlugin;
+
+impl IPlugin for TimerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Timers::new())
+            .add_system(SystemDescriptor::new(TimerSystem).in_stage(Stage::PreUpdate));
+    }
+}
+
+/// 相机组件和自由飞行相机，并在窗口大小变化时更新相机宽高比
+///
+/// 替换为自定义相机时需要同时注册 `Camera` 并处理宽高比。
+pub struct CameraPlugin;
+
+impl IPlugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_component::<Camera>().add_system(CameraSystem);
+    }
+}
+
+/// 脚本组件和驱动行为生命周期的系统
+pub struct ScriptPlugin;
+
+impl IPlugin for ScriptPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_component::<Scriptable>()
+            .add_system(SystemDescriptor::new(ScriptSystem).after("CameraSystem"));
+    }
+}
+
+/// 渲染组件和渲染系统，没有 OpenGL 上下文时只注册组件
+///
+/// 渲染系统查询 `Camera`，这里也注册它，禁用或替换 `CameraPlugin` 时同样可用。
+pub struct RenderPlugin;
+
+impl IPlugin for RenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_component::<Camera>()
+            .register_component::<Light>()
+            .register_component::<Renderable>();
+
+        if app.with_config(|config| config.headless) != HeadlessMode::NoGl {
+            app.add_system(SystemDescriptor::new(RenderSystem::default()).in_stage(Stage::Render));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use glfw::{Glfw, Monitor, PWindow, SwapInterval};
+use log::warn;
+
+use crate::{
+    CursorMode, MonitorInfo, MonitorSelection, VideoMode, VideoModeSelection, WindowCommand,
+    WindowMode, WindowState,
+};
+
+/// 把 `WindowState` 中记录的修改应用到 glfw 窗口上
+#[derive(Default)]
+pub(crate) struct WindowController {
+    // 离开窗口模式前的位置和大小，返回窗口模式时恢复
+    windowed_geometry: Option<((i32, i32), (i32, i32))>,
+}
+
+impl WindowController {
+    /// 应用本帧的修改，没有窗口时只更新状态
+    pub(crate) fn apply(
+        &mut self,
+        glfw: Option<&mut Glfw>,
+        window: Option<&mut PWindow>,
+        state: &mut WindowState,
+    ) {
+        let commands = state.take_commands();
+        let (Some(glfw), Some(window)) = (glfw, window) else {
+            for command in commands {
+                Self::apply_without_window(command, state);
+            }
+            return;
+        };
+
+        for command in commands {
+            match command {
+                WindowCommand::SetMode(mode) => self.set_mode(glfw, window, state, mode),
+                WindowCommand::SetCursorMode(cursor_mode) => {
+                    window.set_cursor_mode(to_glfw_cursor_mode(cursor_mode));
+                    state.set_cursor_mode_state(cursor_mode);
+                }
+                WindowCommand::SetVSync(v_sync) => {
+                    set_swap_interval(glfw, v_sync);
+                    state.set_v_sync_state(v_sync);
+                }
+                WindowCommand::SetTitle(title) => {
+                    window.set_title(&title);
+                    state.set_title_state(title);
+                }
+                // 大小、位置和最小化状态由窗口事件更新
+                WindowCommand::SetSize(width, height) => {
+                    window.set_size(width as i32, height as i32);
+                }
+                WindowCommand::SetPosition(x, y) => window.set_pos(x, y),
+                WindowCommand::Minimize => window.iconify(),
+                WindowCommand::Maximize => window.maximize(),
+                WindowCommand::Restore => window.restore(),
+            }
+        }
+    }
+
+    fn apply_without_window(command: WindowCommand, state: &mut WindowState) {
+        match command {
+            WindowCommand::SetMode(mode) => state.set_mode_state(mode),
+            WindowCommand::SetCursorMode(cursor_mode) => state.set_cursor_mode_state(cursor_mode),
+            WindowCommand::SetVSync(v_sync) => state.set_v_sync_state(v_sync),
+            WindowCommand::SetTitle(title) => state.set_title_state(title),
+            WindowCommand::SetSize(width, height) => state.set_size_state(width, height),
+            WindowCommand::SetPosition(x, y) => state.set_position_state(x, y),
+            WindowCommand::Minimize => state.set_minimized(true),
+            WindowCommand::Maximize => state.set_maximized(true),
+            WindowCommand::Restore => {
+                state.set_minimized(false);
+                state.set_maximized(false);
+            }
+        }
+    }
+
+    fn set_mode(
+        &mut self,
+        glfw: &mut Glfw,
+        window: &mut PWindow,
+        state: &mut WindowState,
+        mode: WindowMode,
+    ) {
+        if mode == state.mode() {
+            return;
+        }
+        if state.mode() == WindowMode::Windowed {
+            self.windowed_geometry = Some((window.get_pos(), window.get_size()));
+        }
+
+        let applied = match mode {
+            WindowMode::Windowed => {
+                let ((x, y), (width, height)) = self
+                    .windowed_geometry
+                    .take()
+                    .unwrap_or((window.get_pos(), window.get_size()));
+                window.set_decorated(true);
+                window.set_monitor(
+                    glfw::WindowMode::Windowed,
+                    x,
+                    y,
+                    width as u32,
+                    height as u32,
+                    None,
+                );
+                true
+            }
+            WindowMode::Borderless(selection) => {
+                let area = glfw.with_connected_monitors(|_, monitors| {
+                    let monitor = select_monitor(monitors, selection, window)?;
+                    let video_mode = monitor.get_video_mode()?;
+                    let (x, y) = monitor.get_pos();
+                    Some((x, y, video_mode.width, video_mode.height))
+                });
+                match area {
+                    Some((x, y, width, height)) => {
+                        window.set_decorated(false);
+                        window.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            WindowMode::Fullscreen(selection, video_mode) => {
+                glfw.with_connected_monitors(|_, monitors| {
+                    let Some(monitor) = select_monitor(monitors, selection, window) else {
+                        return false;
+                    };
+                    let Some(video_mode) = select_video_mode(monitor, video_mode) else {
+                        return false;
+                    };
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor),
+                        0,
+                        0,
+                        video_mode.width,
+                        video_mode.height,
+                        Some(video_mode.refresh_rate),
+                    );
+                    true
+                })
+            }
+        };
+
+        if !applied {
+            warn!("no monitor available for window mode {:?}", mode);
+            return;
+        }
+        // 部分平台切换显示器后会重置交换间隔
+        set_swap_interval(glfw, state.v_sync());
+        state.set_mode_state(mode);
+    }
+}
+
+/// 读取所有显示器，第一个为主显示器
+pub(crate) fn read_monitors(glfw: &mut Glfw) -> Vec<MonitorInfo> {
+    glfw.with_connected_monitors(|_, monitors| {
+        monitors
+            .iter()
+            .map(|monitor| MonitorInfo {
+                name: monitor.get_name().unwrap_or_default(),
+                position: monitor.get_pos(),
+                current_mode: monitor.get_video_mode().map(to_video_mode),
+                video_modes: monitor
+                    .get_video_modes()
+                    .into_iter()
+                    .map(to_video_mode)
+                    .collect(),
+            })
+            .collect()
+    })
+}
+
+pub(crate) fn to_glfw_cursor_mode(cursor_mode: CursorMode) -> glfw::CursorMode {
+    match cursor_mode {
+        CursorMode::Normal => glfw::CursorMode::Normal,
+        CursorMode::Hidden => glfw::CursorMode::Hidden,
+        CursorMode::Captured => glfw::CursorMode::Disabled,
+    }
+}
+
+pub(crate) fn set_swap_interval(glfw: &mut Glfw, v_sync: bool) {
+    if v_sync {
+        glfw.set_swap_interval(SwapInterval::Sync(1));
+    } else {
+        glfw.set_swap_interval(SwapInterval::None);
+    }
+}
+
+fn to_video_mode(video_mode: glfw::VidMode) -> VideoMode {
+    VideoMode {
+        width: video_mode.width,
+        height: video_mode.height,
+        refresh_rate: video_mode.refresh_rate,
+    }
+}
+
+fn select_monitor<'a>(
+    monitors: &'a [&'a mut Monitor],
+    selection: MonitorSelection,
+    window: &PWindow,
+) -> Option<&'a Monitor> {
+    let selected = match selection {
+        MonitorSelection::Primary => None,
+        MonitorSelection::Index(index) => monitors.get(index),
+        MonitorSelection::Current => {
+            let (x, y) = window.get_pos();
+            let (width, height) = window.get_size();
+            let center = (x + width / 2, y + height / 2);
+            monitors.iter().find(|monitor| {
+                let (mx, my) = monitor.get_pos();
+                monitor.get_video_mode().is_some_and(|mode| {
+                    (mx..mx + mode.width as i32).contains(&center.0)
+                        && (my..my + mode.height as i32).contains(&center.1)
+                })
+            })
+        }
+    };
+    selected.or(monitors.first()).map(|monitor| &**monitor)
+}
+
+/// 找不到指定的视频模式时选择分辨率最接近的，再比较刷新率
+fn select_video_mode(monitor: &Monitor, selection: VideoModeSelection) -> Option<VideoMode> {
+    let current = monitor.get_video_mode().map(to_video_mode);
+    let VideoModeSelection::Specific(target) = selection else {
+        return current;
+    };
+    monitor
+        .get_video_modes()
+        .into_iter()
+        .map(to_video_mode)
+        .min_by_key(|mode| {
+            (
+                mode.width.abs_diff(target.width) + mode.height.abs_diff(target.height),
+                mode.refresh_rate.abs_diff(target.refresh_rate),
+            )
+        })
+        .or(current)
+}
@@ -116,6 +116,7 @@ impl Transform {
 
     /// 设置平移
     pub fn set_translation(&mut self, translation: Translation) {
+        self.is_dirty.set(true);
         self.translation = translation;
     }
 
@@ -132,6 +133,7 @@ impl Transform {
 
     /// 设置缩放
     pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.is_dirty.set(true);
         self.scaling = scaling;
     }
 
@@ -148,6 +150,7 @@ impl Transform {
 
     /// 设置旋转
     pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.is_dirty.set(true);
         self.rotation = rotation;
     }
 
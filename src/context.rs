@@ -3,6 +3,9 @@ mod ecs;
 mod event;
 mod input;
 mod pipeline;
+mod profiler;
+mod time;
+mod timer;
 mod window;
 
 pub use asset::*;
@@ -10,19 +13,22 @@ pub use ecs::*;
 pub use event::*;
 pub use input::*;
 pub use pipeline::*;
+pub use profiler::*;
+pub use time::*;
+pub use timer::*;
 pub use window::*;
 
 use crate::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// 应用上下文
+///
+/// 事件队列、输入状态、窗口状态和渲染管线作为资源存放在 World 中，
+/// 通过 `world.resource::<InputState>()` 等方式获取。
 pub struct AppContext {
     pub app_config: RefCell<AppConfig>,
-    pub event_queue: RefCell<AppEventQueue>,
-    pub input_state: RefCell<InputState>,
-    pub window_state: RefCell<WindowState>,
     pub asset_manager: RefCell<AssetManager>,
-    pub pipeline: RefCell<Pipeline>,
     pub world: Rc<RefCell<World>>,
 }
 
@@ -35,15 +41,31 @@ impl AppContext {
         }
 
         let init_resolution = config.resolution;
+        let fixed_dt = 1.0 / config.fixed_update_fps as f64;
+
+        let mut world = World::new_with_default_registry();
+        world.insert_resource(AppEventQueue::new());
+        world.insert_resource(InputState::new());
+        let mut window_state = WindowState::new(init_resolution);
+        window_state.set_title_state(config.title.clone());
+        window_state.set_v_sync_state(config.v_sync);
+        window_state.set_mode(config.window_mode);
+        if config.headless == HeadlessMode::Disabled {
+            window_state.set_cursor_mode(config.cursor_mode);
+        }
+        world.insert_resource(window_state);
+        world.insert_resource(pipeline);
+        world.insert_resource(SystemCommands::new());
+        world.insert_resource(SystemErrorLog::new());
+        world.insert_resource(FixedTime::new(fixed_dt));
+        world.insert_resource(Time::new());
+        world.add_event::<AppExit>();
+        world.insert_resource(Profiler::new(config.profiler, config.profiler_history));
 
         Self {
             app_config: RefCell::new(config),
-            event_queue: RefCell::new(AppEventQueue::new()),
-            input_state: RefCell::new(InputState::new()),
-            window_state: RefCell::new(WindowState::new(init_resolution)),
             asset_manager: RefCell::new(AssetManager::new()),
-            pipeline: RefCell::new(pipeline),
-            world: Rc::new(RefCell::new(World::new_with_default_registry())),
+            world: Rc::new(RefCell::new(world)),
         }
     }
 }
@@ -100,4 +122,11 @@ impl AppContext {
     {
         f(&mut self.world.borrow_mut())
     }
+
+    pub fn with_commands<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Commands) -> R,
+    {
+        f(&self.world.borrow().commands())
+    }
 }
@@ -38,4 +38,13 @@ impl AssetManager {
             framebuffer_manager,
         }
     }
+
+    /// 释放所有资产，需要在 OpenGL 上下文销毁前调用
+    pub fn clear(&self) {
+        self.framebuffer_manager.borrow_mut().clear();
+        self.material_manager.borrow_mut().clear();
+        self.mesh_manager.borrow_mut().clear();
+        self.shader_manager.borrow_mut().clear();
+        self.texture_manager.borrow_mut().clear();
+    }
 }
@@ -128,6 +128,14 @@ impl FramebufferManager {
         Ok(())
     }
 
+    /// 清空所有 framebuffer，附带的纹理一并删除
+    pub fn clear(&mut self) {
+        let handles: Vec<_> = self.framebuffers.keys().collect();
+        for handle in handles {
+            let _ = self.remove(handle);
+        }
+    }
+
     pub(crate) fn bind(&self, handle: FramebufferHandle) -> Result<(), FramebufferError> {
         let Some(fb) = self.framebuffers.get(handle) else {
             return Err(FramebufferError::InvalidHandle);
@@ -54,6 +54,11 @@ impl MaterialManager {
         self.materials.remove(handle);
     }
 
+    /// 清空所有材质
+    pub fn clear(&mut self) {
+        self.materials.clear();
+    }
+
     pub fn get_builder(
         &mut self,
         shader_handle: ShaderHandle,
@@ -30,6 +30,17 @@ impl InstanceBuffer {
         self.vbo = vbo;
     }
 
+    /// 释放缓冲
+    pub fn release(&mut self) {
+        if self.vbo != 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.vbo);
+            }
+            self.vbo = 0;
+            self.capacity = 0;
+        }
+    }
+
     pub fn upload(&mut self, matrices: &Vec<Mat4>) {
         unsafe {
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
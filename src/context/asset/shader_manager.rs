@@ -36,4 +36,9 @@ impl ShaderManager {
     pub fn remove(&mut self, handle: ShaderHandle) {
         self.shaders.remove(handle);
     }
+
+    /// 清空所有shader
+    pub fn clear(&mut self) {
+        self.shaders.clear();
+    }
 }
@@ -50,6 +50,11 @@ impl TextureManager {
     pub fn remove(&mut self, handle: TextureHandle) {
         self.textures.remove(handle);
     }
+
+    /// 清空所有纹理
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
 }
 
 // ============ Texture2D 相关方法 ============
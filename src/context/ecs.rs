@@ -1,9 +1,23 @@
+mod bundle;
+mod commands;
 mod component;
+mod events;
+mod parallel;
+mod query;
+mod schedule;
 mod system;
+mod system_error;
 mod world;
 mod implements;
 
+pub use bundle::*;
+pub use commands::*;
 pub use component::*;
+pub use events::*;
 pub use implements::*;
+pub use parallel::*;
+pub use query::*;
+pub use schedule::*;
 pub use system::*;
+pub use system_error::*;
 pub use world::*;
@@ -0,0 +1,128 @@
+use crate::{EntityHandle, IComponent, World};
+
+/// 可以一次性添加到实体上的一组组件
+///
+/// 单个组件、由 bundle 组成的元组（最多 12 个，可嵌套）都实现了该 trait，
+/// 具名结构体可以通过 `#[derive(Bundle)]` 实现。
+pub trait ComponentBundle {
+    fn add_to_entity(self, world: &World, entity: EntityHandle);
+}
+
+// 为单个组件实现
+impl<T: IComponent> ComponentBundle for T {
+    fn add_to_entity(self, world: &World, entity: EntityHandle) {
+        world.add_component(entity, self);
+    }
+}
+
+impl ComponentBundle for () {
+    fn add_to_entity(self, _world: &World, _entity: EntityHandle) {}
+}
+
+// 为元组实现，元素本身也可以是 bundle
+macro_rules! impl_bundle_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: ComponentBundle),+> ComponentBundle for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn add_to_entity(self, world: &World, entity: EntityHandle) {
+                let ($($name,)+) = self;
+                $($name.add_to_entity(world, entity);)+
+            }
+        }
+    };
+}
+
+impl_bundle_for_tuple!(B1);
+impl_bundle_for_tuple!(B1, B2);
+impl_bundle_for_tuple!(B1, B2, B3);
+impl_bundle_for_tuple!(B1, B2, B3, B4);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7, B8);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7, B8, B9);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7, B8, B9, B10);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7, B8, B9, B10, B11);
+impl_bundle_for_tuple!(B1, B2, B3, B4, B5, B6, B7, B8, B9, B10, B11, B12);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bundle;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    #[derive(Debug, PartialEq)]
+    struct Speed(u32);
+    #[derive(Debug, PartialEq)]
+    struct Tag;
+
+    impl IComponent for Health {}
+    impl IComponent for Speed {}
+    impl IComponent for Tag {}
+
+    #[derive(Bundle)]
+    struct Movement {
+        speed: Speed,
+        // 字段本身可以是 bundle
+        extra: (Tag, ()),
+    }
+
+    #[derive(Bundle)]
+    struct Unit(Health, Movement);
+
+    fn world() -> World {
+        let mut world = World::new_with_default_registry();
+        world.register_component::<Health>();
+        world.register_component::<Speed>();
+        world.register_component::<Tag>();
+        world
+    }
+
+    fn components(world: &World, entity: EntityHandle) -> (Option<u32>, Option<u32>, bool) {
+        (
+            world.get_manager::<Health>().get(entity).map(|h| h.0),
+            world.get_manager::<Speed>().get(entity).map(|s| s.0),
+            world.get_manager::<Tag>().has(entity),
+        )
+    }
+
+    #[test]
+    fn nested_tuple_bundle_adds_every_component() {
+        let mut world = world();
+        let entity = world.spawn_entity_with((Health(1), ((Speed(2),), Tag)));
+        assert_eq!(components(&world, entity), (Some(1), Some(2), true));
+    }
+
+    #[test]
+    fn derived_bundle_adds_fields_and_nested_bundles() {
+        let mut world = world();
+        let entity = world.spawn_entity_with(Unit(
+            Health(3),
+            Movement {
+                speed: Speed(4),
+                extra: (Tag, ()),
+            },
+        ));
+        assert_eq!(components(&world, entity), (Some(3), Some(4), true));
+    }
+
+    #[test]
+    fn derived_bundle_can_be_nested_in_tuples() {
+        let mut world = world();
+        let movement = Movement {
+            speed: Speed(5),
+            extra: (Tag, ()),
+        };
+        let entity = world.spawn_entity_with((Health(6), movement));
+        assert_eq!(components(&world, entity), (Some(6), Some(5), true));
+    }
+
+    #[test]
+    fn twelve_element_tuple_is_a_bundle() {
+        let mut world = world();
+        let entity =
+            world.spawn_entity_with(((), (), (), (), (), (), (), (), (), (), Speed(7), Tag));
+        assert_eq!(components(&world, entity), (None, Some(7), true));
+    }
+}
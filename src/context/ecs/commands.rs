@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+
+use super::{ComponentBundle, EntityHandle, World};
+use crate::IComponent;
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+/// 积压的世界操作
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Vec<Command>,
+}
+
+impl CommandQueue {
+    /// 增加一条命令
+    pub fn push<F>(&mut self, command: F)
+    where
+        F: FnOnce(&mut World) + 'static,
+    {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// 取出所有命令
+    pub(crate) fn take(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+/// 命令记录器
+///
+/// 在系统或脚本只持有 `&World`（组件管理器可能正被借用）时记录操作，
+/// 由 `SystemDispatcher` 在每个系统运行结束后统一执行。
+pub struct Commands<'w> {
+    world: &'w World,
+    queue: &'w RefCell<CommandQueue>,
+}
+
+impl<'w> Commands<'w> {
+    pub(crate) fn new(world: &'w World, queue: &'w RefCell<CommandQueue>) -> Self {
+        Self { world, queue }
+    }
+
+    /// 生成实体，句柄立即可用，组件在同步点添加
+    pub fn spawn<B: ComponentBundle + 'static>(&self, bundle: B) -> EntityHandle {
+        let entity = self.world.reserve_entity();
+        self.add(move |world| {
+            if world.contains(entity) {
+                bundle.add_to_entity(world, entity);
+            }
+        });
+        entity
+    }
+
+    /// 删除实体
+    pub fn despawn(&self, entity: EntityHandle) {
+        self.add(move |world| world.despawn_entity(entity));
+    }
+
+    /// 给实体添加组件
+    pub fn add_component<T: IComponent>(&self, entity: EntityHandle, component: T) {
+        self.add(move |world| {
+            if world.contains(entity) {
+                world.add_component(entity, component);
+            }
+        });
+    }
+
+    /// 删除实体的组件
+    pub fn remove_component<T: IComponent>(&self, entity: EntityHandle) {
+        self.add(move |world| {
+            world.remove_component::<T>(entity);
+        });
+    }
+
+    /// 记录自定义操作
+    pub fn add<F>(&self, command: F)
+    where
+        F: FnOnce(&mut World) + 'static,
+    {
+        self.queue.borrow_mut().push(command);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppConfig, AppContext, ISystem, SystemDescriptor, SystemDispatcher};
+    use std::error::Error;
+    use std::rc::Rc;
+
+    struct Marker(u32);
+
+    impl IComponent for Marker {}
+
+    fn world() -> World {
+        let mut world = World::new_with_default_registry();
+        world.register_component::<Marker>();
+        world
+    }
+
+    #[test]
+    fn spawn_is_deferred_until_applied() {
+        let mut world = world();
+        let entity = world.commands().spawn(Marker(1));
+        // 句柄立即可用，组件在同步点才添加
+        assert!(world.contains(entity));
+        assert!(!world.get_manager::<Marker>().has(entity));
+
+        world.apply_commands();
+        assert_eq!(world.get_manager::<Marker>().get(entity).unwrap().0, 1);
+    }
+
+    #[test]
+    fn commands_queued_by_commands_are_applied_in_same_pass() {
+        let mut world = world();
+        let entity = world.spawn_entity();
+        world.commands().add(move |world| {
+            world.add_component(entity, Marker(1));
+            world.commands().add(move |world| {
+                world.get_manager_mut::<Marker>().get_mut(entity).unwrap().0 += 1;
+                world.commands().spawn(Marker(10));
+            });
+        });
+
+        world.apply_commands();
+        let markers = world.get_manager::<Marker>();
+        assert_eq!(markers.get(entity).unwrap().0, 2);
+        assert_eq!(markers.len(), 2);
+    }
+
+    #[test]
+    fn commands_for_despawned_entities_are_skipped() {
+        let mut world = world();
+        let entity = world.spawn_entity();
+        let commands = world.commands();
+        commands.despawn(entity);
+        commands.add_component(entity, Marker(1));
+        commands.remove_component::<Marker>(entity);
+
+        world.apply_commands();
+        assert!(!world.contains(entity));
+        assert!(world.get_manager::<Marker>().is_empty());
+    }
+
+    /// 生成一个 Marker，并记录当时能否看到它
+    struct Spawner(Rc<RefCell<Vec<usize>>>);
+
+    impl ISystem for Spawner {
+        fn name(&self) -> &str {
+            "Spawner"
+        }
+
+        fn update(
+            &mut self,
+            app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            world.commands().spawn(Marker(0));
+            self.0.borrow_mut().push(world.query::<&Marker>()?.len());
+            Ok(())
+        }
+    }
+
+    /// 记录能看到的 Marker 数量
+    struct Counter(Rc<RefCell<Vec<usize>>>);
+
+    impl ISystem for Counter {
+        fn name(&self) -> &str {
+            "Counter"
+        }
+
+        fn update(
+            &mut self,
+            app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            self.0.borrow_mut().push(world.query::<&Marker>()?.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn commands_from_a_system_are_applied_when_it_ends() {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .register_component::<Marker>();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(Spawner(seen.clone()));
+        dispatcher.add_system(SystemDescriptor::new(Counter(seen.clone())).after("Spawner"));
+
+        dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        dispatcher.run_systems(app_context, 0.0).unwrap();
+        assert_eq!(*seen.borrow(), [0, 1, 1, 2]);
+    }
+}
@@ -1,75 +1,211 @@
 use slotmap::SecondaryMap;
 
-use crate::AppContext;
 use std::any::Any;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::marker::PhantomData;
 
-use super::EntityHandle;
-
-// 实体 ID 定义
-type EntityId = usize;
+use super::{EntityHandle, World};
 
+/// 组件
+///
+/// 生命周期钩子由 `World` 的 `add_component` / `remove_component` / `despawn_entity`
+/// （以及对应的 Commands）触发。调用 `on_add` 时该类型的管理器处于只读借用状态，
+/// 钩子中不要可变借用同类型的管理器，需要修改时使用 `world.commands()`。
+/// `on_replace` / `on_remove` 调用时旧组件已经从管理器中取出，可以移走其中的资源。
 pub trait IComponent: Any {
-    fn update(
-        &mut self,
-        _entity_id: EntityId,
-        _app_context: Rc<RefCell<AppContext>>,
-        _delta_dt: f64,
-    ) {
+    /// 组件首次添加到实体后调用
+    fn on_add(&self, _entity: EntityHandle, _world: &World) {}
+
+    /// 组件被同类型的新组件覆盖后，以旧组件调用
+    fn on_replace(&mut self, _entity: EntityHandle, _world: &World) {}
+
+    /// 组件从实体移除后调用，删除实体时同样会调用
+    fn on_remove(&mut self, _entity: EntityHandle, _world: &World) {}
+}
+
+/// 组件的添加和修改时刻
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u32,
+    pub changed: u32,
+}
+
+impl ComponentTicks {
+    fn new(tick: u32) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    /// 是否在 last_run 之后添加
+    pub fn is_added(&self, last_run: u32) -> bool {
+        self.added > last_run
     }
 
-    fn fixed_update(
-        &mut self,
-        _entity_id: EntityId,
-        _app_context: Rc<RefCell<AppContext>>,
-        _delta_dt: f64,
-    ) {
+    /// 是否在 last_run 之后修改（添加也算修改）
+    pub fn is_changed(&self, last_run: u32) -> bool {
+        self.changed > last_run
     }
 }
 
+struct ComponentCell<T> {
+    value: T,
+    ticks: ComponentTicks,
+}
+
+/// 组件管理器，使用稀疏集合存储
+///
+/// 组件紧密排列在 `dense` 中，`entities` 记录每一行所属的实体，
+/// `sparse` 把实体映射到行号。遍历直接扫描连续内存，按实体查找是一次数组索引。
+/// 删除时用最后一行填补空位，所以行的顺序不固定。
 pub struct ComponentManager<T: IComponent> {
-    components: SecondaryMap<EntityHandle, T>,
+    dense: Vec<ComponentCell<T>>,
+    entities: Vec<EntityHandle>,
+    sparse: SecondaryMap<EntityHandle, usize>,
+    // 被删除的实体及删除时刻
+    removed: Vec<(EntityHandle, u32)>,
+    // 当前时刻，由 World 在可变借用时设置
+    change_tick: u32,
 }
 
 impl<T: IComponent> ComponentManager<T> {
     pub fn new() -> Self {
         Self {
-            components: SecondaryMap::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+            sparse: SecondaryMap::new(),
+            removed: Vec::new(),
+            change_tick: 0,
         }
     }
 
-    pub fn add(&mut self, entity: EntityHandle, component: T) {
-        self.components.insert(entity, component);
+    /// 添加组件，返回被覆盖的旧组件
+    ///
+    /// 不会触发生命周期钩子，外部只能通过 `World::add_component` 添加组件。
+    pub(crate) fn add(&mut self, entity: EntityHandle, component: T) -> Option<T> {
+        let tick = self.change_tick;
+        match self.sparse.get(entity) {
+            // 覆盖已有组件只算修改
+            Some(&row) => {
+                let cell = &mut self.dense[row];
+                cell.ticks.changed = tick;
+                Some(std::mem::replace(&mut cell.value, component))
+            }
+            None => {
+                self.sparse.insert(entity, self.dense.len());
+                self.entities.push(entity);
+                self.dense.push(ComponentCell {
+                    value: component,
+                    ticks: ComponentTicks::new(tick),
+                });
+                None
+            }
+        }
     }
 
-    pub fn remove(&mut self, entity: EntityHandle) -> Option<T> {
-        self.components.remove(entity)
+    /// 移除组件，不会触发生命周期钩子，外部只能通过 `World::remove_component` 移除组件
+    pub(crate) fn remove(&mut self, entity: EntityHandle) -> Option<T> {
+        let row = self.sparse.remove(entity)?;
+        let cell = self.dense.swap_remove(row);
+        self.entities.swap_remove(row);
+        // 最后一行被移到了 row
+        if let Some(&moved) = self.entities.get(row) {
+            self.sparse[moved] = row;
+        }
+        self.removed.push((entity, self.change_tick));
+        Some(cell.value)
     }
 
     /// 获取组件的不可变引用
     pub fn get(&self, entity: EntityHandle) -> Option<&T> {
-        self.components.get(entity)
+        self.sparse.get(entity).map(|&row| &self.dense[row].value)
     }
 
-    /// 获取组件的可变引用
+    /// 获取组件的可变引用，会标记为已修改
     pub fn get_mut(&mut self, entity: EntityHandle) -> Option<&mut T> {
-        self.components.get_mut(entity)
+        let tick = self.change_tick;
+        let row = *self.sparse.get(entity)?;
+        let cell = &mut self.dense[row];
+        cell.ticks.changed = tick;
+        Some(&mut cell.value)
     }
 
     /// 检查实体是否有该组件
     pub fn has(&self, entity: EntityHandle) -> bool {
-        self.components.contains_key(entity)
+        self.sparse.contains_key(entity)
+    }
+
+    /// 组件数量
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// 拥有该组件的所有实体，顺序与 `iter` 一致
+    pub fn entities(&self) -> &[EntityHandle] {
+        &self.entities
+    }
+
+    /// 获取组件的添加和修改时刻
+    pub fn get_ticks(&self, entity: EntityHandle) -> Option<ComponentTicks> {
+        self.sparse.get(entity).map(|&row| self.dense[row].ticks)
+    }
+
+    /// 组件是否在 last_run 之后添加
+    pub fn is_added(&self, entity: EntityHandle, last_run: u32) -> bool {
+        self.get_ticks(entity)
+            .is_some_and(|ticks| ticks.is_added(last_run))
+    }
+
+    /// 组件是否在 last_run 之后修改
+    pub fn is_changed(&self, entity: EntityHandle, last_run: u32) -> bool {
+        self.get_ticks(entity)
+            .is_some_and(|ticks| ticks.is_changed(last_run))
+    }
+
+    /// 在 last_run 之后被删除该组件的实体
+    pub fn removed_since(&self, last_run: u32) -> impl Iterator<Item = EntityHandle> + '_ {
+        self.removed
+            .iter()
+            .filter(move |(_, tick)| *tick > last_run)
+            .map(|(entity, _)| *entity)
     }
 
     /// 返回所有 (EntityHandle, &T) 的迭代器
-    pub fn iter(&self) -> slotmap::secondary::Iter<'_, EntityHandle, T> {
-        self.components.iter()
+    pub fn iter(&self) -> impl Iterator<Item = (EntityHandle, &T)> {
+        self.entities
+            .iter()
+            .copied()
+            .zip(self.dense.iter().map(|cell| &cell.value))
+    }
+
+    /// 返回所有 (EntityHandle, &mut T) 的迭代器，遍历到的组件会标记为已修改
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityHandle, &mut T)> {
+        let tick = self.change_tick;
+        self.entities
+            .iter()
+            .copied()
+            .zip(self.dense.iter_mut().map(move |cell| {
+                cell.ticks.changed = tick;
+                &mut cell.value
+            }))
+    }
+
+    /// 拆出每一行的可变引用，供查询按实体逐个取出
+    pub(crate) fn cell_slots(&mut self) -> CellSlots<'_, T> {
+        CellSlots {
+            sparse: &self.sparse,
+            cells: self.dense.as_mut_ptr(),
+            tick: self.change_tick,
+            _marker: PhantomData,
+        }
     }
 
-    /// 返回所有 (EntityHandle, &mut T) 的迭代器
-    pub fn iter_mut(&mut self) -> slotmap::secondary::IterMut<'_, EntityHandle, T> {
-        self.components.iter_mut()
+    pub(crate) fn change_tick(&self) -> u32 {
+        self.change_tick
     }
 
     /// 查找第一个符合条件的组件，返回 (实体句柄, 组件引用)
@@ -77,8 +213,7 @@ impl<T: IComponent> ComponentManager<T> {
     where
         F: FnMut(&T) -> bool,
     {
-        // iter() 返回的是 (EntityHandle, &T)
-        self.components.iter().find(|(_, comp)| predicate(comp))
+        self.iter().find(|(_, comp)| predicate(comp))
     }
 
     /// 查找第一个符合条件的组件，返回 (实体句柄, 组件可变引用)
@@ -86,15 +221,42 @@ impl<T: IComponent> ComponentManager<T> {
     where
         F: FnMut(&T) -> bool,
     {
-        // iter_mut() 返回的是 (EntityHandle, &mut T)
-        self.components.iter_mut().find(|(_, comp)| predicate(comp))
+        let row = self.dense.iter().position(|cell| predicate(&cell.value))?;
+        let cell = &mut self.dense[row];
+        cell.ticks.changed = self.change_tick;
+        Some((self.entities[row], &mut cell.value))
+    }
+}
+
+/// 管理器中每一行的可变引用，按实体取出时不需要额外分配
+pub struct CellSlots<'q, T> {
+    sparse: &'q SecondaryMap<EntityHandle, usize>,
+    cells: *mut ComponentCell<T>,
+    tick: u32,
+    _marker: PhantomData<&'q mut [ComponentCell<T>]>,
+}
+
+impl<'q, T> CellSlots<'q, T> {
+    /// 取出实体的组件并标记为已修改
+    ///
+    /// # Safety
+    ///
+    /// 同一个 `CellSlots` 中每个实体最多只能取出一次，否则会产生别名的可变引用。
+    pub(crate) unsafe fn take(&mut self, entity: EntityHandle) -> Option<&'q mut T> {
+        let row = *self.sparse.get(entity)?;
+        // SAFETY: sparse 中的行号都小于 dense 的长度，调用方保证每一行只被取出一次
+        let cell = unsafe { &mut *self.cells.add(row) };
+        cell.ticks.changed = self.tick;
+        Some(&mut cell.value)
     }
 }
 
 pub trait IComponentManager: Any {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
-    fn remove_abstract(&mut self, entity: EntityHandle);
+    fn remove_abstract(&mut self, entity: EntityHandle) -> Option<Box<dyn IComponent>>;
+    fn set_change_tick(&mut self, tick: u32);
+    fn clear_removed(&mut self, before: u32);
 }
 
 impl<T: IComponent + 'static> IComponentManager for ComponentManager<T> {
@@ -105,7 +267,106 @@ impl<T: IComponent + 'static> IComponentManager for ComponentManager<T> {
         self
     }
 
-    fn remove_abstract(&mut self, entity: EntityHandle) {
-        self.remove(entity);
+    fn remove_abstract(&mut self, entity: EntityHandle) -> Option<Box<dyn IComponent>> {
+        self.remove(entity)
+            .map(|component| Box::new(component) as Box<dyn IComponent>)
+    }
+
+    fn set_change_tick(&mut self, tick: u32) {
+        self.change_tick = tick;
+    }
+
+    fn clear_removed(&mut self, before: u32) {
+        self.removed.retain(|(_, tick)| *tick > before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Value(u32);
+
+    impl IComponent for Value {}
+
+    fn manager_with(count: usize) -> (ComponentManager<Value>, Vec<EntityHandle>) {
+        let mut world = World::new_with_default_registry();
+        let entities: Vec<EntityHandle> = (0..count).map(|_| world.spawn_entity()).collect();
+        let mut manager = ComponentManager::new();
+        manager.set_change_tick(1);
+        for (i, &entity) in entities.iter().enumerate() {
+            manager.add(entity, Value(i as u32));
+        }
+        (manager, entities)
+    }
+
+    #[test]
+    fn overwriting_counts_as_change_not_add() {
+        let (mut manager, e) = manager_with(1);
+        assert_eq!(
+            manager.get_ticks(e[0]),
+            Some(ComponentTicks {
+                added: 1,
+                changed: 1
+            })
+        );
+        assert!(manager.is_added(e[0], 0));
+        assert!(!manager.is_added(e[0], 1));
+
+        manager.set_change_tick(2);
+        assert_eq!(manager.add(e[0], Value(7)).map(|old| old.0), Some(0));
+        assert!(!manager.is_added(e[0], 1));
+        assert!(manager.is_changed(e[0], 1));
+        assert!(!manager.is_changed(e[0], 2));
+    }
+
+    #[test]
+    fn mutable_access_marks_changed() {
+        let (mut manager, e) = manager_with(3);
+
+        manager.set_change_tick(2);
+        let _ = manager.get(e[0]);
+        manager.get_mut(e[1]).unwrap().0 += 1;
+        assert!(!manager.is_changed(e[0], 1));
+        assert!(manager.is_changed(e[1], 1));
+
+        manager.set_change_tick(3);
+        manager.iter_mut().for_each(|_| {});
+        assert!(e.iter().all(|&entity| manager.is_changed(entity, 2)));
+
+        // 查询只标记真正取出的组件
+        manager.set_change_tick(4);
+        let mut slots = manager.cell_slots();
+        unsafe { slots.take(e[2]) }.unwrap().0 += 1;
+        assert!(!manager.is_changed(e[0], 3));
+        assert!(manager.is_changed(e[2], 3));
+    }
+
+    #[test]
+    fn removal_records_are_kept_until_cleared() {
+        let (mut manager, e) = manager_with(3);
+
+        manager.set_change_tick(2);
+        manager.remove(e[0]);
+        manager.set_change_tick(4);
+        manager.remove(e[1]);
+        assert_eq!(manager.removed_since(1).collect::<Vec<_>>(), [e[0], e[1]]);
+        assert_eq!(manager.removed_since(2).collect::<Vec<_>>(), [e[1]]);
+
+        // 只清理不晚于 before 的记录
+        manager.clear_removed(2);
+        assert_eq!(manager.removed_since(0).collect::<Vec<_>>(), [e[1]]);
+        manager.clear_removed(4);
+        assert_eq!(manager.removed_since(0).count(), 0);
+    }
+
+    #[test]
+    fn removal_keeps_remaining_rows_reachable() {
+        let (mut manager, e) = manager_with(3);
+        manager.remove(e[0]);
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.get(e[1]).unwrap().0, 1);
+        assert_eq!(manager.get(e[2]).unwrap().0, 2);
+        assert!(!manager.has(e[0]));
     }
 }
@@ -0,0 +1,216 @@
+use std::cell::RefMut;
+use std::marker::PhantomData;
+
+struct EventInstance<T> {
+    id: usize,
+    event: T,
+}
+
+/// 类型化的事件通道，作为资源存放在 World 中
+///
+/// 使用双缓冲：每帧结束时交换缓冲并清空较旧的一个，事件因此存活两帧，
+/// fixed_update 中发送的事件不会在 update 读取前丢失。
+pub struct Events<T> {
+    // 上一帧发送的事件
+    previous: Vec<EventInstance<T>>,
+    // 本帧发送的事件
+    current: Vec<EventInstance<T>>,
+    event_count: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            event_count: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(EventInstance {
+            id: self.event_count,
+            event,
+        });
+        self.event_count += 1;
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// 交换缓冲，丢弃两帧前的事件
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// 当前存活的事件数量
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 从旧到新遍历所有存活的事件
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter_from(0)
+    }
+
+    fn iter_from(&self, cursor: usize) -> impl Iterator<Item = &T> {
+        self.previous
+            .iter()
+            .chain(self.current.iter())
+            .filter(move |instance| instance.id >= cursor)
+            .map(|instance| &instance.event)
+    }
+}
+
+/// 事件发送端
+pub struct EventWriter<'w, T> {
+    events: RefMut<'w, Events<T>>,
+}
+
+impl<'w, T> EventWriter<'w, T> {
+    pub(crate) fn new(events: RefMut<'w, Events<T>>) -> Self {
+        Self { events }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.events.send_batch(events);
+    }
+}
+
+/// 事件读取端，自带读取游标，每个读取者只会读到一次同一个事件
+///
+/// 通常保存在系统或脚本中，跨帧保留游标：
+///
+/// ```ignore
+/// let world = ctx.world.borrow();
+/// let events = world.resource::<Events<DamageDealt>>();
+/// for damage in self.damage_reader.read(&events) {
+///     // ...
+/// }
+/// ```
+pub struct EventReader<T> {
+    cursor: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self {
+            cursor: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> EventReader<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 读取上次读取之后的新事件
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let cursor = self.cursor;
+        self.cursor = events.event_count;
+        events.iter_from(cursor)
+    }
+
+    /// 未读事件数量
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.iter_from(self.cursor).count()
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+
+    /// 跳过所有未读事件
+    pub fn clear(&mut self, events: &Events<T>) {
+        self.cursor = events.event_count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(reader: &mut EventReader<u32>, events: &Events<u32>) -> Vec<u32> {
+        reader.read(events).copied().collect()
+    }
+
+    #[test]
+    fn events_live_for_two_updates() {
+        let mut events = Events::new();
+        events.send(1);
+        events.update();
+        events.send(2);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [1, 2]);
+
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [2]);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn each_reader_keeps_its_own_cursor() {
+        let mut events = Events::new();
+        let mut first = EventReader::new();
+        let mut second = EventReader::new();
+        events.send_batch([1, 2]);
+
+        assert_eq!(read(&mut first, &events), [1, 2]);
+        assert!(first.is_empty(&events));
+        events.send(3);
+        assert_eq!(read(&mut first, &events), [3]);
+        assert_eq!(second.len(&events), 3);
+        assert_eq!(read(&mut second, &events), [1, 2, 3]);
+        assert!(read(&mut second, &events).is_empty());
+    }
+
+    #[test]
+    fn reader_misses_events_older_than_two_updates() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+        events.send(1);
+        events.update();
+        events.send(2);
+        events.update();
+        events.send(3);
+
+        assert_eq!(read(&mut reader, &events), [2, 3]);
+    }
+
+    #[test]
+    fn clear_skips_unread_events() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+        events.send(1);
+        reader.clear(&events);
+        events.send(2);
+
+        assert_eq!(read(&mut reader, &events), [2]);
+    }
+}
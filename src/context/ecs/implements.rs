@@ -1,5 +1,7 @@
+mod bundle;
 mod component;
 mod system;
 
+pub use bundle::*;
 pub use component::*;
 pub use system::*;
\ No newline at end of file
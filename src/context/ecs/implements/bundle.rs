@@ -0,0 +1,43 @@
+use crate::{Bundle, Camera, Light, Renderable, Transform};
+
+/// 相机实体
+#[derive(Bundle)]
+pub struct CameraBundle {
+    pub transform: Transform,
+    pub camera: Camera,
+}
+
+impl CameraBundle {
+    pub fn new(camera: Camera, transform: Transform) -> Self {
+        Self { transform, camera }
+    }
+}
+
+/// 光源实体
+#[derive(Bundle)]
+pub struct LightBundle {
+    pub transform: Transform,
+    pub light: Light,
+}
+
+impl LightBundle {
+    pub fn new(light: Light, transform: Transform) -> Self {
+        Self { transform, light }
+    }
+}
+
+/// 可渲染的网格实体
+#[derive(Bundle)]
+pub struct MeshBundle {
+    pub transform: Transform,
+    pub renderable: Renderable,
+}
+
+impl MeshBundle {
+    pub fn new(renderable: Renderable, transform: Transform) -> Self {
+        Self {
+            transform,
+            renderable,
+        }
+    }
+}
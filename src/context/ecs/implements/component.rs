@@ -1,10 +1,13 @@
 mod light;
 mod camera;
+mod hierarchy;
 mod transform;
 mod renderable;
 mod script;
 
 pub use camera::*;
+pub use hierarchy::*;
 pub use light::*;
 pub use renderable::*;
-pub use script::*;
\ No newline at end of file
+pub use script::*;
+pub use transform::*;
\ No newline at end of file
@@ -1,6 +1,6 @@
 use glam::Mat4;
 
-use crate::{FramebufferHandle, IComponent, MaterialHandle, Resolution};
+use crate::{EntityHandle, FramebufferHandle, IComponent, MaterialHandle, Resolution, World};
 // use cgmath::{Deg, Matrix4, Ortho, PerspectiveFov, Rad};
 
 /// 投影类型
@@ -30,14 +30,26 @@ pub struct Camera {
     pub near_plane: f32,
     pub far_plane: f32,
     pub projection_type: ProjectionType,
-    pub(crate) is_initialized: bool,
     pub(crate) is_active: bool, // 是否为主相机
     pub target: RenderTarget,
     pub order: i32, // 渲染顺序，较小的值先渲染
     pub postprocess_materials: Vec<MaterialHandle>,
+    // 还没有按窗口分辨率设置过比例，由 CameraSystem 在相机第一次激活时设置
+    pub(crate) needs_setup: bool,
 }
 
-impl IComponent for Camera {}
+impl IComponent for Camera {
+    fn on_remove(&mut self, entity: EntityHandle, world: &World) {
+        // 通知 RenderSystem 释放该相机的临时 framebuffer
+        if let Some(mut queue) = world.get_resource_mut::<RemovedCameras>() {
+            queue.0.push(entity);
+        }
+    }
+}
+
+/// 已删除的相机实体，由 RenderSystem 释放对应的 GPU 资源
+#[derive(Default)]
+pub(crate) struct RemovedCameras(pub(crate) Vec<EntityHandle>);
 
 impl Camera {
     /// 创建一个默认的相机
@@ -48,11 +60,11 @@ impl Camera {
             near_plane: 0.1,
             far_plane: 100.0,
             projection_type: ProjectionType::Perspective,
-            is_initialized: false,
             is_active,
             target: RenderTarget::Screen,
             order: 0,
             postprocess_materials: vec![],
+            needs_setup: true,
         }
     }
 
@@ -0,0 +1,52 @@
+use thiserror::Error;
+
+use crate::{EntityHandle, IComponent, World};
+
+#[derive(Error, Debug)]
+pub enum HierarchyError {
+    #[error("Entity does not exist")]
+    EntityNotFound,
+    #[error("Entity can not be its own ancestor")]
+    CycleDetected,
+}
+
+/// 父实体
+#[derive(Debug, Clone, Copy)]
+pub struct Parent(pub(crate) EntityHandle);
+
+impl IComponent for Parent {
+    // 无论通过 `detach` 还是直接 `remove_component` 移除，都要同步父实体的子列表
+    fn on_remove(&mut self, entity: EntityHandle, world: &World) {
+        world.unlink_child(self.0, entity);
+    }
+}
+
+impl Parent {
+    pub fn get(&self) -> EntityHandle {
+        self.0
+    }
+}
+
+/// 子实体列表
+#[derive(Debug, Clone, Default)]
+pub struct Children(pub(crate) Vec<EntityHandle>);
+
+impl IComponent for Children {}
+
+impl Children {
+    pub fn iter(&self) -> impl Iterator<Item = EntityHandle> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, entity: EntityHandle) -> bool {
+        self.0.contains(&entity)
+    }
+}
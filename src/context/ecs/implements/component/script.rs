@@ -1,8 +1,14 @@
 use crate::IComponent;
-use crate::{AppContext, EntityHandle};
+use crate::{AppContext, EntityHandle, World};
 use std::{cell::RefCell, rc::Rc};
 
+/// 脚本行为
+///
+/// 生命周期由 ScriptSystem 驱动，顺序为
+/// `on_enable` → `on_start` → `on_update` / `on_fixed_update` → `on_disable` → `on_destroy`。
 pub trait IBehavior: 'static {
+    /// 第一次被 ScriptSystem 运行前调用一次
+    fn on_start(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>) {}
     fn on_update(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>, _dt: f32) {}
     fn on_fixed_update(
         &mut self,
@@ -11,27 +17,151 @@ pub trait IBehavior: 'static {
         _dt: f32,
     ) {
     }
+    /// 从禁用变为启用时调用，添加时已启用的行为也会调用一次，实体重新激活时也会调用
+    fn on_enable(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>) {}
+    /// 从启用变为禁用时调用，实体停用或销毁前也会调用
+    fn on_disable(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>) {}
+    /// 实体被删除或行为被移除后调用，只对已经 start 的行为调用
+    fn on_destroy(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>) {}
+}
+
+/// 行为及其生命周期状态
+pub(crate) struct BehaviorSlot {
+    pub(crate) behavior: Box<dyn IBehavior>,
+    pub(crate) enabled: bool,
+    // 已经通知过 on_enable 且还没有 on_disable
+    pub(crate) is_active: bool,
+    pub(crate) is_started: bool,
+}
+
+impl BehaviorSlot {
+    fn new(behavior: Box<dyn IBehavior>, enabled: bool) -> Self {
+        Self {
+            behavior,
+            enabled,
+            is_active: false,
+            is_started: false,
+        }
+    }
+}
+
+/// 等待 ScriptSystem 调用 on_destroy 的行为，作为资源存放在 World 中
+#[derive(Default)]
+pub(crate) struct DestroyedBehaviors(pub(crate) Vec<(EntityHandle, BehaviorSlot)>);
+
+/// 延迟的行为启用状态修改，作为资源存放在 World 中
+///
+/// 行为回调运行时 Scriptable 处于可变借用状态，行为不能直接修改自己或其他行为，
+/// 需要通过这里记录，ScriptSystem 在回调结束后统一应用。
+/// ```ignore
+/// let context = context.borrow();
+/// let world = context.world.borrow();
+/// world.resource_mut::<BehaviorCommands>().disable(entity, 0);
+/// ```
+#[derive(Default)]
+pub struct BehaviorCommands {
+    queue: Vec<(EntityHandle, usize, bool)>,
+}
+
+impl BehaviorCommands {
+    pub fn enable(&mut self, entity: EntityHandle, index: usize) {
+        self.set_enabled(entity, index, true);
+    }
+
+    pub fn disable(&mut self, entity: EntityHandle, index: usize) {
+        self.set_enabled(entity, index, false);
+    }
+
+    /// 记录实体上第 index 个行为的启用状态，效果同 `Scriptable::set_enabled`
+    pub fn set_enabled(&mut self, entity: EntityHandle, index: usize, enabled: bool) {
+        self.queue.push((entity, index, enabled));
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<(EntityHandle, usize, bool)> {
+        std::mem::take(&mut self.queue)
+    }
 }
 
 pub struct Scriptable {
-    pub behaviors: Vec<Box<dyn IBehavior>>,
+    pub(crate) behaviors: Vec<BehaviorSlot>,
+    // 通过 remove 移除、等待销毁的行为
+    pub(crate) removed: Vec<BehaviorSlot>,
 }
 
-impl IComponent for Scriptable {}
+impl IComponent for Scriptable {
+    fn on_replace(&mut self, entity: EntityHandle, world: &World) {
+        self.destroy_all(entity, world);
+    }
+
+    fn on_remove(&mut self, entity: EntityHandle, world: &World) {
+        self.destroy_all(entity, world);
+    }
+}
 
 impl Scriptable {
     pub fn new() -> Self {
         Self {
             behaviors: Vec::new(),
+            removed: Vec::new(),
         }
     }
 
-    pub fn add<B: IBehavior>(&mut self, behavior: B) {
-        self.behaviors.push(Box::new(behavior));
+    /// 添加行为，返回其下标
+    pub fn add<B: IBehavior>(&mut self, behavior: B) -> usize {
+        self.behaviors
+            .push(BehaviorSlot::new(Box::new(behavior), true));
+        self.behaviors.len() - 1
     }
 
     pub fn with<B: IBehavior>(mut self, behavior: B) -> Self {
         self.add(behavior);
         self
     }
+
+    /// 添加一个初始禁用的行为
+    pub fn with_disabled<B: IBehavior>(mut self, behavior: B) -> Self {
+        self.behaviors
+            .push(BehaviorSlot::new(Box::new(behavior), false));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.behaviors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.behaviors.is_empty()
+    }
+
+    /// 启用或禁用行为，on_enable / on_disable 在 ScriptSystem 下一次运行时调用
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(slot) = self.behaviors.get_mut(index) {
+            slot.enabled = enabled;
+        }
+    }
+
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.behaviors.get(index).is_some_and(|slot| slot.enabled)
+    }
+
+    /// 移除行为，后面行为的下标前移，on_destroy 在 ScriptSystem 下一次运行时调用
+    pub fn remove(&mut self, index: usize) {
+        if index < self.behaviors.len() {
+            let slot = self.behaviors.remove(index);
+            self.removed.push(slot);
+        }
+    }
+
+    /// 组件被移除时，把所有行为交给 ScriptSystem 销毁
+    fn destroy_all(&mut self, entity: EntityHandle, world: &World) {
+        let Some(mut destroyed) = world.get_resource_mut::<DestroyedBehaviors>() else {
+            return;
+        };
+        destroyed.0.extend(
+            self.removed
+                .drain(..)
+                .chain(self.behaviors.drain(..))
+                .map(|slot| (entity, slot)),
+        );
+    }
 }
@@ -1,3 +1,159 @@
-use crate::{IComponent, Transform};
+use glam::{Mat4, Quat, Vec3};
+
+use crate::{IComponent, Transform, TransformError};
 
 impl IComponent for Transform {}
+
+/// 世界空间的变换，由 TransformSystem 根据层级关系计算并缓存
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalTransform {
+    matrix: Mat4,
+    // 在两次 fixed_update 之间插值后的矩阵，只用于渲染
+    render_matrix: Mat4,
+}
+
+impl IComponent for GlobalTransform {}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+            render_matrix: Mat4::IDENTITY,
+        }
+    }
+}
+
+impl GlobalTransform {
+    /// 从世界矩阵创建
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        Self {
+            matrix,
+            render_matrix: matrix,
+        }
+    }
+
+    /// 获取世界矩阵
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    /// 渲染使用的世界矩阵，按 `FixedTime::alpha` 在两次 fixed_update 的结果之间插值
+    pub fn render_matrix(&self) -> Mat4 {
+        self.render_matrix
+    }
+
+    pub(crate) fn set_matrix(&mut self, matrix: Mat4, render_matrix: Mat4) {
+        self.matrix = matrix;
+        self.render_matrix = render_matrix;
+    }
+
+    /// 渲染时的变换
+    pub(crate) fn interpolated(&self) -> Self {
+        Self::from_matrix(self.render_matrix)
+    }
+
+    /// 世界空间的位置
+    pub fn translation(&self) -> Vec3 {
+        self.matrix.w_axis.truncate()
+    }
+
+    pub fn get_forward(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::NEG_Z).normalize()
+    }
+
+    pub fn get_right(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::X).normalize()
+    }
+
+    pub fn get_up(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::Y).normalize()
+    }
+
+    /// 获取法线变化矩阵
+    pub(crate) fn to_normal_matrix(self) -> Result<Mat4, TransformError> {
+        let inverse = self.matrix.inverse();
+        if inverse.is_nan() {
+            return Err(TransformError::InverseMatrixFail);
+        }
+
+        Ok(inverse.transpose())
+    }
+
+    pub(crate) fn get_view_matrix(&self) -> Mat4 {
+        Mat4::look_to_rh(self.translation(), self.get_forward(), self.get_up())
+    }
+}
+
+// 局部变换的平移、旋转和缩放
+#[derive(Debug, Clone, Copy)]
+struct Pose {
+    translation: Vec3,
+    rotation: Quat,
+    scaling: Vec3,
+}
+
+impl Pose {
+    fn from_transform(transform: &Transform) -> Self {
+        Self {
+            translation: transform.get_translation().data.into(),
+            rotation: transform.get_rotation().data,
+            scaling: transform.get_scaling().data.into(),
+        }
+    }
+
+    fn lerp(&self, target: &Pose, t: f32) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            self.scaling.lerp(target.scaling, t),
+            self.rotation.slerp(target.rotation, t),
+            self.translation.lerp(target.translation, t),
+        )
+    }
+}
+
+/// 在 fixed_update 中移动过的实体前后两次 fixed_update 的局部变换，由 TransformSystem 添加和维护
+///
+/// 在 fixed_update 中瞬移实体后可以移除该组件，避免渲染出中间位置。
+#[derive(Debug, Clone, Copy)]
+pub struct TransformInterpolation {
+    previous: Pose,
+    current: Pose,
+    // 记录 current 时的时刻
+    tick: u32,
+}
+
+impl IComponent for TransformInterpolation {}
+
+impl TransformInterpolation {
+    pub(crate) fn new(transform: &Transform, tick: u32) -> Self {
+        let pose = Pose::from_transform(transform);
+        Self {
+            previous: pose,
+            current: pose,
+            tick,
+        }
+    }
+
+    /// 记录时刻
+    pub(crate) fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// 进入新的固定步，transform 为 None 时保持不动
+    pub(crate) fn advance(&mut self, transform: Option<&Transform>, tick: u32) {
+        self.previous = self.current;
+        if let Some(transform) = transform {
+            self.current = Pose::from_transform(transform);
+        }
+        self.tick = tick;
+    }
+
+    /// 跳过插值，直接使用 transform
+    pub(crate) fn snap(&mut self, transform: &Transform, tick: u32) {
+        *self = Self::new(transform, tick);
+    }
+
+    /// 插值后的局部矩阵
+    pub(crate) fn matrix(&self, alpha: f32) -> Mat4 {
+        self.previous.lerp(&self.current, alpha)
+    }
+}
@@ -1,7 +1,11 @@
 mod camera_system;
 mod render_system;
 mod script_system;
+mod timer_system;
+mod transform_system;
 
 pub use camera_system::*;
 pub use render_system::*;
-pub use script_system::*;
\ No newline at end of file
+pub use script_system::*;
+pub use timer_system::*;
+pub use transform_system::*;
\ No newline at end of file
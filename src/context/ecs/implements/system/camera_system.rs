@@ -1,4 +1,7 @@
-use crate::{AppContext, AppEvent, Camera, ISystem, Resolution, Rotation, Transform, Translation};
+use crate::{
+    Active, AppContext, AppEvent, AppEventQueue, Camera, CursorMode, EntityHandle, ISystem,
+    InputState, Resolution, Rotation, Transform, Translation, WindowState,
+};
 use glam::{Quat, Vec2, Vec3};
 use glfw::Key;
 use std::cell::RefCell;
@@ -18,15 +21,11 @@ impl ISystem for CameraSystem {
         app_context: Rc<RefCell<AppContext>>,
         _delta_dt: f32,
     ) -> Result<(), Box<dyn Error>> {
-        let config_resolution = {
-            let context = app_context.borrow();
-            let config = context.app_config.borrow();
-            config.resolution
-        };
         // 根据resize事件缩放相机比例
         let resize_data = {
             let app = app_context.borrow();
-            let event_queue = app.event_queue.borrow();
+            let world = app.world.borrow();
+            let event_queue = world.resource::<AppEventQueue>();
             event_queue.all_events().iter().rev().find_map(|event| {
                 if let AppEvent::Resize { width, height } = event {
                     Some((*width, *height))
@@ -39,18 +38,30 @@ impl ISystem for CameraSystem {
         {
             let app = app_context.borrow();
             let world = app.world.borrow();
-            let mut camera_mgr = world.get_manager_mut::<Camera>();
 
-            if let Some((_entity, main_cam)) = camera_mgr.find_mut(|cam| cam.is_active) {
-                if let Some((w, h)) = resize_data {
-                    main_cam.set_aspect_ratio(Resolution::new(w as u32, h as u32));
-                    return Ok(());
+            // 第一次激活的主相机使用当前分辨率，包括创建时未激活、之后才激活的相机
+            let pending: Vec<EntityHandle> = world
+                .query_filtered::<&Camera, Active>()?
+                .iter()
+                .filter(|(_, camera)| camera.is_active && camera.needs_setup)
+                .map(|(entity, _)| entity)
+                .collect();
+            if !pending.is_empty() {
+                let resolution = world.resource::<WindowState>().get_resolution();
+                let mut cameras = world.get_manager_mut::<Camera>();
+                for entity in pending {
+                    if let Some(camera) = cameras.get_mut(entity) {
+                        camera.set_aspect_ratio(resolution);
+                        camera.needs_setup = false;
+                    }
                 }
+            }
 
-                if main_cam.is_initialized == false {
-                    main_cam.set_aspect_ratio(config_resolution);
-                    main_cam.is_initialized = true;
-                    return Ok(());
+            if let Some((w, h)) = resize_data {
+                let mut cameras = world.query_filtered::<&mut Camera, Active>()?;
+                if let Some((_entity, main_cam)) = cameras.iter_mut().find(|(_, cam)| cam.is_active)
+                {
+                    main_cam.set_aspect_ratio(Resolution::new(w as u32, h as u32));
                 }
             }
         }
@@ -66,7 +77,8 @@ impl ISystem for CameraSystem {
         // 1. 先提取所有需要的输入数据，然后立即释放 input_state 的锁
         let (movement, scroll_y, cursor_delta) = {
             let app = app_context.borrow(); // app 是 Ref<AppContext>
-            let input = app.input_state.borrow(); // input 是 Ref<InputState>
+            let world = app.world.borrow();
+            let input = world.resource::<InputState>(); // input 是 Ref<InputState>
 
             let move_dir = if input.is_key_down(Key::W) {
                 Some(CameraMovement::Forward)
@@ -85,29 +97,34 @@ impl ISystem for CameraSystem {
             };
 
             let scroll_y = input.get_scroll_delta().y;
-            let cursor_delta = Vec2::new(input.get_cursor_delta().x, input.get_cursor_delta().y);
+            // 光标未锁定时（例如打开菜单）不转动视角
+            let cursor_delta =
+                if world.resource::<WindowState>().cursor_mode() == CursorMode::Captured {
+                    Vec2::new(input.get_cursor_delta().x, input.get_cursor_delta().y)
+                } else {
+                    Vec2::ZERO
+                };
 
             (move_dir, scroll_y, cursor_delta)
         };
 
         {
             let app = app_context.borrow();
-            let world = app.world.borrow_mut();
+            let world = app.world.borrow();
 
-            let mut camera_mgr = world.get_manager_mut::<Camera>();
-            let mut transform_mgr = world.get_manager_mut::<Transform>();
+            let mut query = world.query_filtered::<(&mut Camera, &mut Transform), Active>()?;
 
-            if let Some((entity, main_cam)) = camera_mgr.find_mut(|cam| cam.is_active) {
-                if let Some(transform) = transform_mgr.get_mut(entity) {
-                    if let Some(m) = movement {
-                        process_move(transform, m, 10.0, delta_dt);
-                    }
-                    if scroll_y != 0.0 {
-                        process_zoom(main_cam, scroll_y, 0.5);
-                    }
-                    // 使用解构出来的浮点数值
-                    process_turn(transform, cursor_delta.x, cursor_delta.y, 0.005, true);
+            if let Some((_entity, main_cam, transform)) =
+                query.iter_mut().find(|(_, cam, _)| cam.is_active)
+            {
+                if let Some(m) = movement {
+                    process_move(transform, m, 10.0, delta_dt);
+                }
+                if scroll_y != 0.0 {
+                    process_zoom(main_cam, scroll_y, 0.5);
                 }
+                // 使用解构出来的浮点数值
+                process_turn(transform, cursor_delta.x, cursor_delta.y, 0.005, true);
             }
         }
 
@@ -1,8 +1,10 @@
 mod global_uniform;
+mod gpu_timer;
 mod uniform_model;
 
 use glam::Mat4;
 pub use global_uniform::*;
+pub use gpu_timer::*;
 pub use uniform_model::*;
 
 use crate::*;
@@ -57,12 +59,22 @@ pub struct RenderSystem {
 
     // render stage
     render_stages: HashMap<PassId, Vec<(MeshHandle, MaterialHandle, EntityHandle)>>,
+
+    // 性能分析开启时为当前帧的编号
+    profile_frame: Option<u64>,
+    gpu_timer: GpuTimer,
+    draw_calls: u32,
 }
 
 impl RenderSystem {
-    fn get_all_cameras(camera_mgr: &ComponentManager<Camera>) -> Vec<(EntityHandle, &Camera)> {
-        let mut cameras: Vec<(EntityHandle, &Camera)> =
-            camera_mgr.iter().map(|(id, cam)| (id, cam)).collect();
+    fn get_all_cameras<'a>(
+        world: &World,
+        camera_mgr: &'a ComponentManager<Camera>,
+    ) -> Vec<(EntityHandle, &'a Camera)> {
+        let mut cameras: Vec<(EntityHandle, &Camera)> = camera_mgr
+            .iter()
+            .filter(|(id, _)| world.is_active_in_hierarchy(*id))
+            .collect();
         if cameras.is_empty() {
             warn!("No cameras found, render nothing");
         }
@@ -139,24 +151,27 @@ impl RenderSystem {
     }
 
     fn get_camera_trasform(
-        transform_mgr: &ComponentManager<Transform>,
+        transform_mgr: &ComponentManager<GlobalTransform>,
         camera_entity: EntityHandle,
-    ) -> Result<&Transform, RenderError> {
+    ) -> Result<GlobalTransform, RenderError> {
         let Some(camera_transform) = transform_mgr.get(camera_entity) else {
             return Err(RenderError::NotFoundCameraTransform);
         };
-        Ok(camera_transform)
+        Ok(camera_transform.interpolated())
     }
 
     fn get_light_shader_data(
+        world: &World,
         light_mgr: &ComponentManager<Light>,
-        transform_mgr: &ComponentManager<Transform>,
+        transform_mgr: &ComponentManager<GlobalTransform>,
     ) -> Result<Vec<LightShaderData>, RenderError> {
         let raw_lights_shader_data = light_mgr
             .iter()
+            .filter(|(entity, _)| world.is_active_in_hierarchy(*entity))
             .map(|(entity, light)| {
                 if let Some(light_transform) = transform_mgr.get(entity) {
-                    return Some(LightShaderData::from_light(light, light_transform));
+                    let light_transform = light_transform.interpolated();
+                    return Some(LightShaderData::from_light(light, &light_transform));
                 }
                 None
             })
@@ -171,36 +186,36 @@ impl RenderSystem {
 
     fn get_render_jobs_of_pass(
         &self,
-        transform_mgr: &ComponentManager<Transform>,
+        transform_mgr: &ComponentManager<GlobalTransform>,
         camera_view_matrix: Mat4,
         pass: &Pass,
         instancing: bool,
     ) -> Result<Vec<RenderJob>, RenderError> {
         let jobs: Vec<RenderJob>;
         if instancing && pass.is_opaque {
-            let mut batch_map: HashMap<(MaterialHandle, MeshHandle), Vec<&Transform>> =
-                HashMap::new();
-            if let Some(batches) = self.render_stages.get(&pass.id) {
-                for (mesh, material, entity) in batches.iter() {
-                    if let Some(transform) = transform_mgr.get(*entity) {
-                        let key = (*material, *mesh);
-                        let entry = batch_map.entry(key).or_insert(vec![]);
-                        entry.push(transform);
+            // render_stages 已按 (材质, 网格) 排序，相邻的相同条目即为一个批次
+            let batches = self
+                .render_stages
+                .get(&pass.id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            jobs = batches
+                .chunk_by(|a, b| (a.0, a.1) == (b.0, b.1))
+                .filter_map(|batch| {
+                    let (mesh, material, _) = batch[0];
+                    let matrices: Vec<Mat4> = batch
+                        .iter()
+                        .filter_map(|(_, _, entity)| transform_mgr.get(*entity))
+                        .map(|t| t.render_matrix())
+                        .collect();
+                    if matrices.is_empty() {
+                        return None;
                     }
-                }
-            }
-
-            jobs = batch_map
-                .into_iter() // 并行处理 HashMap 的每个 Entry
-                .map(|((material, mesh), transform_refs)| {
-                    // 内部并行计算矩阵
-                    let matrices: Vec<Mat4> =
-                        transform_refs.iter().map(|t| t.to_matrix()).collect();
 
                     // 创建具体的 InstancedJob 类型
                     let mut instanced_job = InstancedJob::new(mesh, material);
                     instanced_job.set_transforms(matrices);
-                    RenderJob::Instanced(instanced_job)
+                    Some(RenderJob::Instanced(instanced_job))
                 })
                 .collect();
         } else {
@@ -208,7 +223,7 @@ impl RenderSystem {
             if let Some(batches) = self.render_stages.get(&pass.id) {
                 for (mesh, material, entity) in batches.iter() {
                     let depth = if let Some(transform) = transform_mgr.get(*entity) {
-                        let world_pos_v4 = transform.get_translation().data.to_homogeneous();
+                        let world_pos_v4 = transform.interpolated().translation().extend(1.0);
                         let view_pos = camera_view_matrix * world_pos_v4;
                         -view_pos.z
                     } else {
@@ -235,7 +250,7 @@ impl RenderSystem {
     fn do_render_job(
         &mut self,
         job: &RenderJob,
-        transform_mgr: &ComponentManager<Transform>,
+        transform_mgr: &ComponentManager<GlobalTransform>,
         asset_mgr: &AssetManager,
     ) -> Result<(), RenderError> {
         match job {
@@ -250,13 +265,14 @@ impl RenderSystem {
                 };
 
                 self.global_uniform
-                    .update_model_data(&ModelData::new(transform)?);
+                    .update_model_data(&ModelData::new(&transform.interpolated())?);
 
                 // 绑定 Shader
                 Self::bind_material(&asset_mgr, material_handle)?;
 
                 // 绘制 Mesh
                 Self::draw_mesh(&asset_mgr, mesh_handle)?;
+                self.draw_calls += 1;
 
                 // 卸载材质
                 Self::unbind_material(&asset_mgr, material_handle)?;
@@ -269,6 +285,7 @@ impl RenderSystem {
                 Self::bind_material(&asset_mgr, material_handle)?;
 
                 self.draw_mesh_instanced(&asset_mgr, mesh_handle, transforms)?;
+                self.draw_calls += 1;
 
                 Self::unbind_material(&asset_mgr, material_handle)?;
             }
@@ -401,8 +418,11 @@ impl RenderSystem {
         self.render_stages.clear();
         let world = app_context.world.borrow();
         let renderable_mgr = world.get_manager::<Renderable>();
-        let pipeline = app_context.pipeline.borrow();
+        let pipeline = world.resource::<Pipeline>();
         for (entity, renderable) in renderable_mgr.iter() {
+            if !world.is_active_in_hierarchy(entity) {
+                continue;
+            }
             for pass in &pipeline.passes {
                 if let Some(material) = renderable.get_material(pass.id) {
                     let mesh = renderable.mesh;
@@ -411,6 +431,10 @@ impl RenderSystem {
                 }
             }
         }
+        // 按材质和网格排序，实例化时相同的组合连续存放
+        for stage in self.render_stages.values_mut() {
+            stage.sort_unstable_by_key(|(mesh, material, _)| (*material, *mesh));
+        }
 
         Ok(())
     }
@@ -425,6 +449,11 @@ impl ISystem for RenderSystem {
         self.create_guad_mesh(&app_context.borrow())?;
         self.global_uniform.init();
         self.temp_instance_buffer.init();
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .insert_resource(RemovedCameras::default());
         Ok(())
     }
 
@@ -436,17 +465,22 @@ impl ISystem for RenderSystem {
         let context = app_context.borrow();
         let world = context.world.borrow();
         let asset_mgr = context.asset_manager.borrow();
-        let pipeline = context.pipeline.borrow();
+        let pipeline = world.resource::<Pipeline>();
         let camera_mgr = world.get_manager_mut::<Camera>();
-        let transform_mgr = world.get_manager_mut::<Transform>();
+        let transform_mgr = world.get_manager::<GlobalTransform>();
         let light_mgr = world.get_manager_mut::<Light>();
-        let window_state = context.window_state.borrow();
+        let window_state = world.resource::<WindowState>();
         let window_resolution = window_state.get_resolution();
 
-        let cameras = Self::get_all_cameras(&camera_mgr);
+        self.release_removed_camera_framebuffers(&context, &world);
+        self.begin_profile(&world);
+
+        let cameras = Self::get_all_cameras(&world, &camera_mgr);
+        // 渲染批次与相机无关，每帧只收集和排序一次
+        self.init_render_stage(&context)?;
 
         // 计算光源 shader 信息
-        let lights_shader_data = Self::get_light_shader_data(&light_mgr, &transform_mgr)?;
+        let lights_shader_data = Self::get_light_shader_data(&world, &light_mgr, &transform_mgr)?;
 
         self.global_uniform
             .update_frame_data(&FrameData::new(&lights_shader_data));
@@ -486,12 +520,11 @@ impl ISystem for RenderSystem {
             let view_matrix = camera_transform.get_view_matrix();
 
             self.global_uniform
-                .update_camera_data(&CameraData::new(camera, camera_transform));
-
-            self.init_render_stage(&context)?;
+                .update_camera_data(&CameraData::new(camera, &camera_transform));
 
             // 按 pass 渲染
             for pass in &pipeline.passes {
+                self.gpu_begin(&pass.name);
                 // 应用渲染状态
                 pass.default_state.apply();
 
@@ -510,6 +543,7 @@ impl ISystem for RenderSystem {
                         continue;
                     }
                 }
+                self.gpu_timer.end();
             }
 
             // 相机渲染完成后解绑 rendertarget
@@ -528,10 +562,69 @@ impl ISystem for RenderSystem {
             }
         }
 
+        self.end_profile(&world);
+        Ok(())
+    }
+
+    /// 释放渲染系统创建的 framebuffer、mesh 和缓冲
+    fn shutdown(&mut self, app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+        let context = app_context.borrow();
+        let mut framebuffers: Vec<_> = self
+            .camera_temp_framebuffers
+            .drain()
+            .map(|(_, fb)| fb)
+            .collect();
+        framebuffers.extend(
+            self.ping_pong_framebuffers
+                .iter_mut()
+                .filter_map(Option::take),
+        );
+        for fb in framebuffers {
+            if let Err(e) = context.with_fbr_mgr(|m| m.remove(fb)) {
+                warn!("Failed to release framebuffer: {:?}", e);
+            }
+        }
+        if let Some(quad) = self.fullscreen_quad.take() {
+            context.with_msh_mgr(|m| m.remove(quad));
+        }
+        self.global_uniform.release();
+        self.temp_instance_buffer.release();
+        self.gpu_timer.release();
+        self.render_stages.clear();
         Ok(())
     }
 }
 
+// 性能分析
+impl RenderSystem {
+    /// 取回之前帧的 GPU 耗时，记录当前帧的编号
+    fn begin_profile(&mut self, world: &World) {
+        // 上一帧出错提前返回时可能还有未结束的查询
+        self.gpu_timer.end();
+        self.draw_calls = 0;
+        self.profile_frame = None;
+        let Some(mut profiler) = world.get_resource_mut::<Profiler>() else {
+            return;
+        };
+        self.gpu_timer.collect(&mut profiler);
+        if profiler.is_enabled() {
+            self.profile_frame = Some(profiler.frame_index());
+        }
+    }
+
+    fn end_profile(&mut self, world: &World) {
+        if let Some(mut profiler) = world.get_resource_mut::<Profiler>() {
+            profiler.set_counter("draw_calls", self.draw_calls as f64);
+        }
+    }
+
+    fn gpu_begin(&mut self, name: &str) {
+        if let Some(frame_index) = self.profile_frame {
+            self.gpu_timer.begin(frame_index, name);
+        }
+    }
+}
+
 // 后处理
 impl RenderSystem {
     /// 获取或创建相机的临时 framebuffer
@@ -557,7 +650,10 @@ impl RenderSystem {
         // 不存在就创建新的
         let fb = context.with_fbr_mgr(|m| {
             let texture_config_2d = TextureConfig::default();
-            let texture_config_msaa = TextureConfig::MultiSample { anti_pixel, format_type: FormatType::SRGBA };
+            let texture_config_msaa = TextureConfig::MultiSample {
+                anti_pixel,
+                format_type: FormatType::SRGBA,
+            };
             m.create_multi_sample(resolution, texture_config_msaa, texture_config_2d)
         })?;
 
@@ -565,6 +661,19 @@ impl RenderSystem {
         Ok(fb)
     }
 
+    /// 释放已删除相机的临时 framebuffer
+    fn release_removed_camera_framebuffers(&mut self, context: &AppContext, world: &World) {
+        let removed = std::mem::take(&mut world.resource_mut::<RemovedCameras>().0);
+        for camera_entity in removed {
+            let Some(fb) = self.camera_temp_framebuffers.remove(&camera_entity) else {
+                continue;
+            };
+            if let Err(e) = context.with_fbr_mgr(|m| m.remove(fb)) {
+                warn!("Failed to release temp framebuffer: {:?}", e);
+            }
+        }
+    }
+
     /// 确保 ping-pong framebuffers 存在
     fn ensure_ping_pong_framebuffers(
         &mut self,
@@ -586,15 +695,16 @@ impl RenderSystem {
 
             // 创建新的
             let texture_config_2d = TextureConfig::default();
-            let texture_config_msaa = TextureConfig::MultiSample { anti_pixel, format_type: FormatType::SRGBA };
-            self.ping_pong_framebuffers[0] =
-                Some(context.with_fbr_mgr(|m| {
-                    m.create_multi_sample(resolution, texture_config_msaa, texture_config_2d)
-                })?);
-            self.ping_pong_framebuffers[1] =
-                Some(context.with_fbr_mgr(|m| {
-                    m.create_multi_sample(resolution, texture_config_msaa, texture_config_2d)
-                })?);
+            let texture_config_msaa = TextureConfig::MultiSample {
+                anti_pixel,
+                format_type: FormatType::SRGBA,
+            };
+            self.ping_pong_framebuffers[0] = Some(context.with_fbr_mgr(|m| {
+                m.create_multi_sample(resolution, texture_config_msaa, texture_config_2d)
+            })?);
+            self.ping_pong_framebuffers[1] = Some(context.with_fbr_mgr(|m| {
+                m.create_multi_sample(resolution, texture_config_msaa, texture_config_2d)
+            })?);
 
             self.ping_pong_size = resolution;
         }
@@ -626,13 +736,16 @@ impl RenderSystem {
 
         // 如果只有一个后处理，直接渲染到最终目标
         if materials.len() == 1 {
-            return self.render_postprocess_pass(
+            self.gpu_begin("postprocess 0");
+            let result = self.render_postprocess_pass(
                 context,
                 source_texture,
                 final_target,
                 materials[0],
                 window_resolution,
             );
+            self.gpu_timer.end();
+            return result;
         }
 
         // 多个后处理，需要 ping-pong
@@ -653,13 +766,16 @@ impl RenderSystem {
                 }
             };
 
-            self.render_postprocess_pass(
+            self.gpu_begin(&format!("postprocess {}", i));
+            let result = self.render_postprocess_pass(
                 context,
                 current_source,
                 current_target,
                 material,
                 window_resolution,
-            )?;
+            );
+            self.gpu_timer.end();
+            result?;
 
             // 更新 source
             if !is_last {
@@ -752,5 +868,3 @@ impl RenderSystem {
         Ok(())
     }
 }
-
-// TODO: 清空camera已经删除的fb
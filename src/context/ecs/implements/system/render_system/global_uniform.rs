@@ -24,6 +24,19 @@ impl GlobalUniform {
         }
     }
 
+    /// 释放所有 UBO
+    pub fn release(&mut self) {
+        let ubos = [self.frame_ubo, self.camera_ubo, self.model_ubo];
+        if ubos.iter().any(|&ubo| ubo != 0) {
+            unsafe {
+                gl::DeleteBuffers(3, ubos.as_ptr());
+            }
+        }
+        self.frame_ubo = 0;
+        self.camera_ubo = 0;
+        self.model_ubo = 0;
+    }
+
     pub fn init(&mut self) {
         unsafe {
             let mut ubos = [0u32; 3];
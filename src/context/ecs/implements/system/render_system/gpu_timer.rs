@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use gl::types::*;
+
+use crate::Profiler;
+
+struct PendingQuery {
+    query: GLuint,
+    frame_index: u64,
+    name: String,
+}
+
+/// 使用 GL_TIME_ELAPSED 查询测量 GPU 耗时
+///
+/// 查询结果在几帧之后才可用，每帧开始时取回已完成的结果交给 Profiler。
+/// 同一时刻只能有一个查询在进行，不能嵌套。
+#[derive(Default)]
+pub struct GpuTimer {
+    pending: VecDeque<PendingQuery>,
+    // 结果已取回、可以复用的查询对象
+    free: Vec<GLuint>,
+    running: bool,
+}
+
+impl GpuTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始测量，已有查询在进行时忽略
+    pub fn begin(&mut self, frame_index: u64, name: &str) {
+        if self.running {
+            return;
+        }
+        let query = self.free.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            unsafe {
+                gl::GenQueries(1, &mut query);
+            }
+            query
+        });
+        unsafe {
+            gl::BeginQuery(gl::TIME_ELAPSED, query);
+        }
+        self.pending.push_back(PendingQuery {
+            query,
+            frame_index,
+            name: name.to_string(),
+        });
+        self.running = true;
+    }
+
+    pub fn end(&mut self) {
+        if !self.running {
+            return;
+        }
+        unsafe {
+            gl::EndQuery(gl::TIME_ELAPSED);
+        }
+        self.running = false;
+    }
+
+    /// 按提交顺序取回已完成的查询结果
+    pub fn collect(&mut self, profiler: &mut Profiler) {
+        while let Some(pending) = self.pending.front() {
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectiv(pending.query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+            if available == 0 {
+                break;
+            }
+            let mut elapsed_ns: GLuint64 = 0;
+            unsafe {
+                gl::GetQueryObjectui64v(pending.query, gl::QUERY_RESULT, &mut elapsed_ns);
+            }
+            profiler.record_gpu(pending.frame_index, &pending.name, elapsed_ns as f64 * 1e-9);
+
+            let pending = self.pending.pop_front().unwrap();
+            self.free.push(pending.query);
+        }
+    }
+
+    /// 删除所有查询对象
+    pub fn release(&mut self) {
+        self.end();
+        let queries: Vec<GLuint> = self
+            .pending
+            .drain(..)
+            .map(|pending| pending.query)
+            .chain(self.free.drain(..))
+            .collect();
+        if !queries.is_empty() {
+            unsafe {
+                gl::DeleteQueries(queries.len() as GLsizei, queries.as_ptr());
+            }
+        }
+    }
+}
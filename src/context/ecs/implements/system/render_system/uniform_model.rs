@@ -1,6 +1,6 @@
 use glam::Mat4;
 
-use crate::{Camera, Light, LightData, ProjectionType, Transform, TransformError};
+use crate::{Camera, GlobalTransform, Light, LightData, ProjectionType, TransformError};
 
 /// 相机的shader数据，用来传递给shader
 #[repr(C, align(16))]
@@ -107,10 +107,10 @@ impl CameraShaderData {
         }
     }
 
-    pub fn from_camera(camera: &Camera, transform: &Transform) -> Self {
-        let [x, y, z] = transform.get_rotation().forward().into();
+    pub fn from_camera(camera: &Camera, transform: &GlobalTransform) -> Self {
+        let [x, y, z] = transform.get_forward().into();
         let direction = [x, y, z, 0.0];
-        let [x, y, z] = transform.get_translation().get_arr().into();
+        let [x, y, z] = transform.translation().into();
         let position = [x, y, z, 1.0];
         Self::new(
             if camera.projection_type == ProjectionType::Perspective {
@@ -168,13 +168,13 @@ impl LightShaderData {
         }
     }
 
-    pub fn from_light(light: &Light, transform: &Transform) -> Self {
+    pub fn from_light(light: &Light, transform: &GlobalTransform) -> Self {
         // 预提取通用属性
         let color = light.color.to_arr();
         let intensity = light.intensity;
-        let [x, y, z] = transform.get_translation().get_arr();
+        let [x, y, z] = transform.translation().into();
         let position = [x, y, z, 1.0];
-        let [x, y, z] = transform.get_rotation().forward().into();
+        let [x, y, z] = transform.get_forward().into();
         let direction = [x, y, z, 0.0];
 
         match light.data {
@@ -235,7 +235,7 @@ impl Default for CameraData {
 }
 
 impl CameraData {
-    pub fn new(camera: &Camera, camera_transform: &Transform) -> Self {
+    pub fn new(camera: &Camera, camera_transform: &GlobalTransform) -> Self {
         let view_matrix = camera_transform.get_view_matrix();
         let projection_matrix = camera.get_projection_matrix();
         let camera_shader_data = CameraShaderData::from_camera(camera, camera_transform);
@@ -257,8 +257,8 @@ impl Default for ModelData {
 }
 
 impl ModelData {
-    pub fn new(model_transform: &Transform) -> Result<Self, TransformError> {
-        let model_matrix = model_transform.to_matrix();
+    pub fn new(model_transform: &GlobalTransform) -> Result<Self, TransformError> {
+        let model_matrix = model_transform.matrix();
         let normal_matrix = model_transform.to_normal_matrix()?;
 
         let mut dm = Self::default();
@@ -1,30 +1,143 @@
 use std::{cell::RefCell, error::Error, rc::Rc};
 
-use crate::{AppContext, ISystem, Scriptable};
+use crate::{
+    AppContext, BehaviorCommands, BehaviorSlot, DestroyedBehaviors, EntityHandle, ISystem,
+    Scriptable,
+};
 
 #[derive(Default)]
 pub struct ScriptSystem;
 
+impl ScriptSystem {
+    /// 处理启用状态的变化和 on_start，返回该行为本次是否需要运行
+    ///
+    /// 实体停用时视为行为被禁用，重新激活后恢复。
+    fn prepare_behavior(
+        slot: &mut BehaviorSlot,
+        entity: EntityHandle,
+        entity_active: bool,
+        app_context: &Rc<RefCell<AppContext>>,
+    ) -> bool {
+        let enabled = slot.enabled && entity_active;
+        if enabled != slot.is_active {
+            slot.is_active = enabled;
+            if enabled {
+                slot.behavior.on_enable(entity, app_context.clone());
+            } else {
+                slot.behavior.on_disable(entity, app_context.clone());
+            }
+        }
+
+        if !enabled {
+            return false;
+        }
+
+        if !slot.is_started {
+            slot.is_started = true;
+            slot.behavior.on_start(entity, app_context.clone());
+        }
+        true
+    }
+
+    fn destroy_behavior(
+        mut slot: BehaviorSlot,
+        entity: EntityHandle,
+        app_context: &Rc<RefCell<AppContext>>,
+    ) {
+        if slot.is_active {
+            slot.behavior.on_disable(entity, app_context.clone());
+        }
+        if slot.is_started {
+            slot.behavior.on_destroy(entity, app_context.clone());
+        }
+    }
+
+    /// 销毁随实体或组件一起删除的行为
+    fn destroy_removed_behaviors(app_context: &Rc<RefCell<AppContext>>) {
+        let destroyed = {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            world
+                .get_resource_mut::<DestroyedBehaviors>()
+                .map(|mut destroyed| std::mem::take(&mut destroyed.0))
+                .unwrap_or_default()
+        };
+
+        for (entity, slot) in destroyed {
+            Self::destroy_behavior(slot, entity, app_context);
+        }
+    }
+
+    /// 应用 BehaviorCommands 中积压的启用状态修改
+    fn apply_behavior_commands(app_context: &Rc<RefCell<AppContext>>) {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        let commands = world
+            .get_resource_mut::<BehaviorCommands>()
+            .map(|mut commands| commands.take())
+            .unwrap_or_default();
+        if commands.is_empty() {
+            return;
+        }
+
+        let mut script_mgr = world.get_manager_mut::<Scriptable>();
+        for (entity, index, enabled) in commands {
+            if let Some(script_comp) = script_mgr.get_mut(entity) {
+                script_comp.set_enabled(index, enabled);
+            }
+        }
+    }
+}
+
 impl ISystem for ScriptSystem {
     fn name(&self) -> &str {
         "ScriptSystem"
     }
 
+    fn init(&mut self, app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .insert_resource(DestroyedBehaviors::default());
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .insert_resource(BehaviorCommands::default());
+        Ok(())
+    }
+
     fn update(
         &mut self,
         app_context: Rc<RefCell<AppContext>>,
         dt: f32,
     ) -> Result<(), Box<dyn Error>> {
-        let context = app_context.borrow();
-        let world = context.world.borrow();
+        Self::destroy_removed_behaviors(&app_context);
+        Self::apply_behavior_commands(&app_context);
 
-        let mut script_mgr = world.get_manager_mut::<Scriptable>();
+        {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+
+            let mut script_mgr = world.get_manager_mut::<Scriptable>();
+
+            for (entity, script_comp) in script_mgr.iter_mut() {
+                for slot in script_comp.removed.drain(..) {
+                    Self::destroy_behavior(slot, entity, &app_context);
+                }
 
-        for (entity, script_comp) in script_mgr.iter_mut() {
-            for behavior in script_comp.behaviors.iter_mut() {
-                behavior.on_update(entity, app_context.clone(), dt);
+                let entity_active = world.is_active_in_hierarchy(entity);
+                for slot in script_comp.behaviors.iter_mut() {
+                    if Self::prepare_behavior(slot, entity, entity_active, &app_context) {
+                        slot.behavior.on_update(entity, app_context.clone(), dt);
+                    }
+                }
             }
         }
+
+        // 回调中记录的修改在 Scriptable 的借用释放后应用
+        Self::apply_behavior_commands(&app_context);
         Ok(())
     }
 
@@ -33,17 +146,134 @@ impl ISystem for ScriptSystem {
         app_context: Rc<RefCell<AppContext>>,
         delta_dt: f32,
     ) -> Result<(), Box<dyn Error>> {
-        let context = app_context.borrow();
-        let world = context.world.borrow();
+        Self::destroy_removed_behaviors(&app_context);
+        Self::apply_behavior_commands(&app_context);
 
-        let mut script_mgr = world.get_manager_mut::<Scriptable>();
+        {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+
+            let mut script_mgr = world.get_manager_mut::<Scriptable>();
 
-        for (entity, script_comp) in script_mgr.iter_mut() {
-            for behavior in script_comp.behaviors.iter_mut() {
-                behavior.on_fixed_update(entity, app_context.clone(), delta_dt);
+            for (entity, script_comp) in script_mgr.iter_mut() {
+                for slot in script_comp.removed.drain(..) {
+                    Self::destroy_behavior(slot, entity, &app_context);
+                }
+
+                let entity_active = world.is_active_in_hierarchy(entity);
+                for slot in script_comp.behaviors.iter_mut() {
+                    if Self::prepare_behavior(slot, entity, entity_active, &app_context) {
+                        slot.behavior
+                            .on_fixed_update(entity, app_context.clone(), delta_dt);
+                    }
+                }
             }
         }
 
+        Self::apply_behavior_commands(&app_context);
         Ok(())
     }
+
+    /// 销毁所有行为，之后删除实体时不会再调用 on_destroy
+    fn shutdown(&mut self, app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+        Self::destroy_removed_behaviors(&app_context);
+
+        let slots: Vec<(EntityHandle, BehaviorSlot)> = {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            let mut script_mgr = world.get_manager_mut::<Scriptable>();
+            script_mgr
+                .iter_mut()
+                .flat_map(|(entity, script_comp)| {
+                    script_comp
+                        .removed
+                        .drain(..)
+                        .chain(script_comp.behaviors.drain(..))
+                        .map(move |slot| (entity, slot))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+
+        for (entity, slot) in slots {
+            Self::destroy_behavior(slot, entity, &app_context);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppConfig, IBehavior};
+
+    /// 第一次更新时禁用自己和下一个行为
+    struct DisableOnUpdate {
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl IBehavior for DisableOnUpdate {
+        fn on_update(&mut self, entity: EntityHandle, context: Rc<RefCell<AppContext>>, _dt: f32) {
+            self.log.borrow_mut().push("update");
+            let context = context.borrow();
+            let world = context.world.borrow();
+            let mut commands = world.resource_mut::<BehaviorCommands>();
+            commands.disable(entity, 0);
+            commands.disable(entity, 1);
+        }
+
+        fn on_disable(&mut self, _entity: EntityHandle, _context: Rc<RefCell<AppContext>>) {
+            self.log.borrow_mut().push("disable");
+        }
+    }
+
+    struct Counter {
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl IBehavior for Counter {
+        fn on_update(
+            &mut self,
+            _entity: EntityHandle,
+            _context: Rc<RefCell<AppContext>>,
+            _dt: f32,
+        ) {
+            self.log.borrow_mut().push("counter");
+        }
+    }
+
+    #[test]
+    fn behaviors_can_disable_themselves_and_others() {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let entity = {
+            let context = app_context.borrow();
+            let mut world = context.world.borrow_mut();
+            world.register_component::<Scriptable>();
+            let entity = world.spawn_entity();
+            world.add_component(
+                entity,
+                Scriptable::new()
+                    .with(DisableOnUpdate { log: log.clone() })
+                    .with(Counter { log: log.clone() }),
+            );
+            entity
+        };
+
+        let mut system = ScriptSystem;
+        system.init(app_context.clone()).unwrap();
+        // 同一帧中排在后面的行为仍然运行，修改在回调结束后生效
+        system.update(app_context.clone(), 0.0).unwrap();
+        assert_eq!(*log.borrow(), ["update", "counter"]);
+
+        system.update(app_context.clone(), 0.0).unwrap();
+        assert_eq!(*log.borrow(), ["update", "counter", "disable"]);
+
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        let script_mgr = world.get_manager::<Scriptable>();
+        let script_comp = script_mgr.get(entity).unwrap();
+        assert!(!script_comp.is_enabled(0));
+        assert!(!script_comp.is_enabled(1));
+    }
 }
@@ -0,0 +1,47 @@
+use std::{cell::RefCell, error::Error, rc::Rc};
+
+use crate::{AppContext, ISystem, Timers};
+
+/// 推进 `Timers` 中的定时器和序列
+#[derive(Default)]
+pub struct TimerSystem;
+
+impl ISystem for TimerSystem {
+    fn name(&self) -> &str {
+        "TimerSystem"
+    }
+
+    fn update(
+        &mut self,
+        app_context: Rc<RefCell<AppContext>>,
+        dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        // 本帧新加入的任务从下一帧开始计时
+        let handles = {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            let mut timers = world.resource_mut::<Timers>();
+
+            let handles = timers.handles();
+            // 绑定的实体已被删除，取消任务
+            for (handle, owner) in &handles {
+                if owner.is_some_and(|entity| !world.contains(entity)) {
+                    timers.cancel(*handle);
+                }
+            }
+
+            handles
+                .into_iter()
+                .filter(|(_, owner)| {
+                    owner.is_none_or(|entity| world.is_active_in_hierarchy(entity))
+                })
+                .map(|(handle, _)| handle)
+                .collect::<Vec<_>>()
+        };
+
+        for handle in handles {
+            Timers::tick_task(&app_context, handle, dt);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use std::{cell::RefCell, error::Error, rc::Rc};
+
+use glam::Mat4;
+
+use crate::{
+    AppContext, Children, ComponentManager, EntityHandle, FixedTime, GlobalTransform, ISystem,
+    Parent, Transform, TransformInterpolation, World,
+};
+
+/// 根据父子关系计算每个实体的世界矩阵
+///
+/// fixed_update 中移动过的实体会记录前后两次的局部变换，渲染矩阵按 `FixedTime::alpha` 插值。
+#[derive(Default)]
+pub struct TransformSystem {
+    // 上一次 update 的时刻，之后的修改才算作 fixed_update 中的移动
+    update_tick: u32,
+}
+
+impl ISystem for TransformSystem {
+    fn name(&self) -> &str {
+        "TransformSystem"
+    }
+
+    fn update(
+        &mut self,
+        app_context: Rc<RefCell<AppContext>>,
+        _delta_dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+
+        add_missing_globals(&world);
+
+        // fixed_update 之外修改过的变换不做插值
+        {
+            let tick = world.change_tick();
+            let transform_mgr = world.get_manager::<Transform>();
+            let mut interpolation_mgr = world.get_manager_mut::<TransformInterpolation>();
+            for (entity, interpolation) in interpolation_mgr.iter_mut() {
+                let Some(transform) = transform_mgr.get(entity) else {
+                    continue;
+                };
+                let changed = transform_mgr
+                    .get_ticks(entity)
+                    .is_some_and(|ticks| ticks.is_changed(interpolation.tick()));
+                if changed {
+                    interpolation.snap(transform, tick);
+                }
+            }
+        }
+
+        self.update_tick = world.change_tick();
+        propagate_all(&world);
+
+        Ok(())
+    }
+
+    fn fixed_update(
+        &mut self,
+        app_context: Rc<RefCell<AppContext>>,
+        _delta_dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        let tick = world.change_tick();
+        let last_run = world.last_run_tick().max(self.update_tick);
+
+        let transform_mgr = world.get_manager::<Transform>();
+        let is_changed = |entity: EntityHandle| {
+            transform_mgr
+                .get_ticks(entity)
+                .is_some_and(|ticks| ticks.is_changed(last_run))
+        };
+
+        // 本次固定步中移动过、还没有插值记录的实体从现在开始记录
+        let added: Vec<EntityHandle> = {
+            let interpolation_mgr = world.get_manager::<TransformInterpolation>();
+            transform_mgr
+                .iter()
+                .filter(|(entity, _)| !interpolation_mgr.has(*entity) && is_changed(*entity))
+                .map(|(entity, _)| entity)
+                .collect()
+        };
+
+        let mut interpolation_mgr = world.get_manager_mut::<TransformInterpolation>();
+        for (entity, interpolation) in interpolation_mgr.iter_mut() {
+            let transform = transform_mgr.get(entity).filter(|_| is_changed(entity));
+            interpolation.advance(transform, tick);
+        }
+        for entity in added {
+            if let Some(transform) = transform_mgr.get(entity) {
+                interpolation_mgr.add(entity, TransformInterpolation::new(transform, tick));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 在 Update 阶段之前传播一次世界矩阵
+///
+/// `TransformSystem` 需要在所有 fixed_update 之后记录插值，只能运行在 PostUpdate，
+/// 这个系统让 Update 阶段的系统（如相机和脚本）读到包含本帧 fixed_update 结果的世界矩阵。
+#[derive(Default)]
+pub struct TransformPropagateSystem;
+
+impl ISystem for TransformPropagateSystem {
+    fn name(&self) -> &str {
+        "TransformPropagateSystem"
+    }
+
+    fn update(
+        &mut self,
+        app_context: Rc<RefCell<AppContext>>,
+        _delta_dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        add_missing_globals(&world);
+        propagate_all(&world);
+        Ok(())
+    }
+}
+
+/// 给还没有 GlobalTransform 的实体补上
+fn add_missing_globals(world: &World) {
+    let missing: Vec<EntityHandle> = {
+        let transform_mgr = world.get_manager::<Transform>();
+        let global_mgr = world.get_manager::<GlobalTransform>();
+        transform_mgr
+            .iter()
+            .filter(|(entity, _)| !global_mgr.has(*entity))
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    for entity in missing {
+        world.add_component(entity, GlobalTransform::default());
+    }
+}
+
+/// 从根实体开始计算所有实体的世界矩阵
+fn propagate_all(world: &World) {
+    let alpha = world.resource::<FixedTime>().alpha();
+    let transform_mgr = world.get_manager::<Transform>();
+    let interpolation_mgr = world.get_manager::<TransformInterpolation>();
+    let parent_mgr = world.get_manager::<Parent>();
+    let children_mgr = world.get_manager::<Children>();
+    let mut global_mgr = world.get_manager_mut::<GlobalTransform>();
+    let locals = LocalMatrices {
+        transform_mgr: &transform_mgr,
+        interpolation_mgr: &interpolation_mgr,
+        alpha,
+    };
+
+    for (entity, transform) in transform_mgr.iter() {
+        // 父实体有 Transform 的实体由父实体负责传播
+        let has_parent_transform = parent_mgr
+            .get(entity)
+            .is_some_and(|parent| transform_mgr.has(parent.get()));
+        if has_parent_transform {
+            continue;
+        }
+
+        propagate(
+            entity,
+            transform.to_matrix(),
+            locals.render_matrix(entity, transform),
+            &locals,
+            &children_mgr,
+            &mut global_mgr,
+        );
+    }
+}
+
+/// 计算局部矩阵和插值后的局部矩阵
+struct LocalMatrices<'a> {
+    transform_mgr: &'a ComponentManager<Transform>,
+    interpolation_mgr: &'a ComponentManager<TransformInterpolation>,
+    alpha: f32,
+}
+
+impl LocalMatrices<'_> {
+    fn render_matrix(&self, entity: EntityHandle, transform: &Transform) -> Mat4 {
+        match self.interpolation_mgr.get(entity) {
+            Some(interpolation) => interpolation.matrix(self.alpha),
+            None => transform.to_matrix(),
+        }
+    }
+}
+
+fn propagate(
+    entity: EntityHandle,
+    matrix: Mat4,
+    render_matrix: Mat4,
+    locals: &LocalMatrices,
+    children_mgr: &ComponentManager<Children>,
+    global_mgr: &mut ComponentManager<GlobalTransform>,
+) {
+    if let Some(global) = global_mgr.get_mut(entity) {
+        global.set_matrix(matrix, render_matrix);
+    }
+
+    let Some(children) = children_mgr.get(entity) else {
+        return;
+    };
+
+    for child in children.iter() {
+        if let Some(transform) = locals.transform_mgr.get(child) {
+            propagate(
+                child,
+                matrix * transform.to_matrix(),
+                render_matrix * locals.render_matrix(child, transform),
+                locals,
+                children_mgr,
+                global_mgr,
+            );
+        }
+    }
+}
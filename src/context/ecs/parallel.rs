@@ -0,0 +1,534 @@
+use std::any::{Any, TypeId, type_name};
+use std::cell::{Cell, Ref, RefMut};
+use std::error::Error;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use super::{Access, ComponentManager, IComponent, IComponentManager, QueryError, World};
+
+/// 并行系统声明的组件和资源访问
+///
+/// 调度器据此把互不冲突的并行系统放到同一批次，在线程池中同时运行。
+///
+/// ```ignore
+/// SystemAccess::new()
+///     .read::<Transform>()
+///     .write::<Velocity>()
+///     .read_resource::<Gravity>()
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SystemAccess {
+    components: Vec<(TypeId, &'static str, Access)>,
+    resources: Vec<(TypeId, &'static str, Access)>,
+}
+
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只读组件，组件需要实现 Sync
+    pub fn read<T: IComponent + Sync>(mut self) -> Self {
+        Self::declare::<T>(&mut self.components, Access::Read);
+        self
+    }
+
+    /// 可写组件，组件需要实现 Send
+    pub fn write<T: IComponent + Send>(mut self) -> Self {
+        Self::declare::<T>(&mut self.components, Access::Write);
+        self
+    }
+
+    /// 只读资源，资源需要实现 Sync
+    pub fn read_resource<R: Sync + 'static>(mut self) -> Self {
+        Self::declare::<R>(&mut self.resources, Access::Read);
+        self
+    }
+
+    /// 可写资源，资源需要实现 Send
+    pub fn write_resource<R: Send + 'static>(mut self) -> Self {
+        Self::declare::<R>(&mut self.resources, Access::Write);
+        self
+    }
+
+    /// 两个系统是否可以同时运行：任一类型被一方写入时，另一方不能访问
+    pub fn is_compatible(&self, other: &SystemAccess) -> bool {
+        fn compatible(
+            a: &[(TypeId, &'static str, Access)],
+            b: &[(TypeId, &'static str, Access)],
+        ) -> bool {
+            a.iter().all(|(id, _, access)| {
+                b.iter().all(|(other_id, _, other_access)| {
+                    id != other_id || (*access == Access::Read && *other_access == Access::Read)
+                })
+            })
+        }
+        compatible(&self.components, &other.components)
+            && compatible(&self.resources, &other.resources)
+    }
+
+    // 同一类型重复声明时，写访问覆盖读访问
+    fn declare<T: 'static>(items: &mut Vec<(TypeId, &'static str, Access)>, access: Access) {
+        let type_id = TypeId::of::<T>();
+        match items.iter_mut().find(|(id, _, _)| *id == type_id) {
+            Some(item) => {
+                if access == Access::Write {
+                    item.2 = Access::Write;
+                }
+            }
+            None => items.push((type_id, type_name::<T>(), access)),
+        }
+    }
+}
+
+/// 可以在线程池中运行的系统
+///
+/// 并行系统不能直接访问 `AppContext`，只能通过 `SystemData` 访问 `access` 中声明的数据。
+/// 需要 OpenGL 或 `AppContext` 的系统（如 RenderSystem）应实现 `ISystem`，它们总是在主线程运行。
+pub trait IParallelSystem: Send {
+    fn name(&self) -> &str;
+    fn access(&self) -> SystemAccess;
+    fn update(
+        &mut self,
+        _data: &SystemData<'_>,
+        _delta_dt: f32,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn fixed_update(
+        &mut self,
+        _data: &SystemData<'_>,
+        _delta_dt: f32,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// 主线程为一个批次借出的管理器和资源，批次结束后释放
+pub(crate) enum BorrowGuard<'w> {
+    Manager(Ref<'w, Box<dyn IComponentManager>>),
+    ManagerMut(RefMut<'w, Box<dyn IComponentManager>>),
+    Resource(Ref<'w, Box<dyn Any>>),
+    ResourceMut(RefMut<'w, Box<dyn Any>>),
+}
+
+struct DataEntry<P: ?Sized> {
+    type_id: TypeId,
+    name: &'static str,
+    ptr: *mut P,
+    access: Access,
+    // 与 RefCell 相同：正数为只读借用数，-1 为可写借用
+    borrow: Cell<isize>,
+}
+
+impl<P: ?Sized> DataEntry<P> {
+    fn acquire(&self, access: Access) {
+        let state = self.borrow.get();
+        match access {
+            Access::Read => {
+                assert!(state >= 0, "{} 已被可写借用", self.name);
+                self.borrow.set(state + 1);
+            }
+            Access::Write => {
+                assert!(self.access == Access::Write, "{} 未声明为可写", self.name);
+                assert!(state == 0, "{} 已被借用", self.name);
+                self.borrow.set(-1);
+            }
+        }
+    }
+}
+
+/// 并行系统可以访问的数据
+pub struct SystemData<'w> {
+    components: Vec<DataEntry<dyn IComponentManager>>,
+    resources: Vec<DataEntry<dyn Any>>,
+    last_run_tick: u32,
+    change_tick: u32,
+    _marker: PhantomData<&'w World>,
+}
+
+// SAFETY: SystemData 中的裸指针只在下面几点同时成立时才会被解引用：
+//
+// 1. 不同系统之间不会别名。`borrow` 对每个声明的管理器和资源调用 World 中 RefCell 的
+//    try_borrow / try_borrow_mut，得到的 Ref / RefMut 存放在主线程的 guards 中，直到整个批次
+//    运行结束才释放。同一批次里两个系统写同一类型，或一个读一个写时，后借的一方会得到
+//    AlreadyBorrowed 错误而不会运行。所以即使调度器的冲突判断有误，也不会有两个线程同时拿到
+//    同一数据的可变引用。`SystemAccess::is_compatible` 只负责让这类系统进入不同批次依次运行。
+// 2. 同一系统内部不会别名。每个 DataEntry 的 `borrow` 计数和 RefCell 规则相同，
+//    `acquire` 在已有可写借用时拒绝新的借用，在已有任何借用时拒绝可写借用，
+//    未声明为可写的数据也不能可写借用。DataRef / DataMut 释放时归还计数。
+//    Cell 不是 Sync，SystemData 也就不是 Sync，计数只会被持有它的那个线程修改。
+// 3. 跨线程访问的类型满足要求。只读访问要求 T: Sync，可写访问要求 T: Send，
+//    由 SystemAccess 的声明方法和下面的访问方法共同约束；按 TypeId 查找保证指针的实际类型就是 T。
+// 4. 指针不会悬空。`borrow` 只在 crate 内由 `SystemDispatcher::run_parallel_batch` 调用，
+//    它在 guards 之后创建批次的 SystemData，并在 rayon::scope 返回后、guards 释放前丢弃它们。
+unsafe impl Send for SystemData<'_> {}
+
+impl<'w> SystemData<'w> {
+    /// 在主线程按声明借出数据，借用保存在 guards 中
+    pub(crate) fn borrow(
+        world: &'w World,
+        access: &SystemAccess,
+        last_run_tick: u32,
+        guards: &mut Vec<BorrowGuard<'w>>,
+    ) -> Result<Self, QueryError> {
+        let mut components = Vec::new();
+        for &(type_id, name, item_access) in &access.components {
+            let ptr: *mut dyn IComponentManager = match item_access {
+                Access::Read => {
+                    let guard = world.try_borrow_manager_erased(type_id, name)?;
+                    let ptr = &**guard as *const dyn IComponentManager as *mut _;
+                    guards.push(BorrowGuard::Manager(guard));
+                    ptr
+                }
+                Access::Write => {
+                    let mut guard = world.try_borrow_manager_erased_mut(type_id, name)?;
+                    let ptr = &mut **guard as *mut dyn IComponentManager;
+                    guards.push(BorrowGuard::ManagerMut(guard));
+                    ptr
+                }
+            };
+            components.push(DataEntry {
+                type_id,
+                name,
+                ptr,
+                access: item_access,
+                borrow: Cell::new(0),
+            });
+        }
+
+        let mut resources = Vec::new();
+        for &(type_id, name, item_access) in &access.resources {
+            let ptr: *mut dyn Any = match item_access {
+                Access::Read => {
+                    let guard = world.try_borrow_resource_erased(type_id, name)?;
+                    let ptr = &**guard as *const dyn Any as *mut _;
+                    guards.push(BorrowGuard::Resource(guard));
+                    ptr
+                }
+                Access::Write => {
+                    let mut guard = world.try_borrow_resource_erased_mut(type_id, name)?;
+                    let ptr = &mut **guard as *mut dyn Any;
+                    guards.push(BorrowGuard::ResourceMut(guard));
+                    ptr
+                }
+            };
+            resources.push(DataEntry {
+                type_id,
+                name,
+                ptr,
+                access: item_access,
+                borrow: Cell::new(0),
+            });
+        }
+
+        Ok(Self {
+            components,
+            resources,
+            last_run_tick,
+            change_tick: world.change_tick(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// 该系统上一次运行的时刻，用于变化检测
+    pub fn last_run_tick(&self) -> u32 {
+        self.last_run_tick
+    }
+
+    /// 当前时刻
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick
+    }
+
+    /// 获取只读组件管理器，未声明时 panic
+    pub fn components<T: IComponent + Sync>(&self) -> DataRef<'_, ComponentManager<T>> {
+        let entry = Self::find(&self.components, TypeId::of::<T>(), type_name::<T>());
+        entry.acquire(Access::Read);
+        // SAFETY: 见 SystemData 的 Send 实现
+        let manager = unsafe { &*entry.ptr };
+        DataRef {
+            value: manager
+                .as_any()
+                .downcast_ref::<ComponentManager<T>>()
+                .expect("类型转换失败"),
+            borrow: &entry.borrow,
+        }
+    }
+
+    /// 获取可写组件管理器，未声明为可写时 panic
+    pub fn components_mut<T: IComponent + Send>(&self) -> DataMut<'_, ComponentManager<T>> {
+        let entry = Self::find(&self.components, TypeId::of::<T>(), type_name::<T>());
+        entry.acquire(Access::Write);
+        // SAFETY: 见 SystemData 的 Send 实现，acquire 保证没有其他借用
+        let manager = unsafe { &mut *entry.ptr };
+        DataMut {
+            value: manager
+                .as_any_mut()
+                .downcast_mut::<ComponentManager<T>>()
+                .expect("类型转换失败"),
+            borrow: &entry.borrow,
+        }
+    }
+
+    /// 获取只读资源，未声明时 panic
+    pub fn resource<R: Sync + 'static>(&self) -> DataRef<'_, R> {
+        let entry = Self::find(&self.resources, TypeId::of::<R>(), type_name::<R>());
+        entry.acquire(Access::Read);
+        // SAFETY: 见 SystemData 的 Send 实现
+        let resource = unsafe { &*entry.ptr };
+        DataRef {
+            value: resource.downcast_ref::<R>().expect("类型转换失败"),
+            borrow: &entry.borrow,
+        }
+    }
+
+    /// 获取可写资源，未声明为可写时 panic
+    pub fn resource_mut<R: Send + 'static>(&self) -> DataMut<'_, R> {
+        let entry = Self::find(&self.resources, TypeId::of::<R>(), type_name::<R>());
+        entry.acquire(Access::Write);
+        // SAFETY: 见 SystemData 的 Send 实现，acquire 保证没有其他借用
+        let resource = unsafe { &mut *entry.ptr };
+        DataMut {
+            value: resource.downcast_mut::<R>().expect("类型转换失败"),
+            borrow: &entry.borrow,
+        }
+    }
+
+    fn find<'a, P: ?Sized>(
+        entries: &'a [DataEntry<P>],
+        type_id: TypeId,
+        name: &'static str,
+    ) -> &'a DataEntry<P> {
+        entries
+            .iter()
+            .find(|entry| entry.type_id == type_id)
+            .unwrap_or_else(|| panic!("系统未声明访问: {}", name))
+    }
+}
+
+/// SystemData 借出的只读引用
+pub struct DataRef<'a, T> {
+    value: &'a T,
+    borrow: &'a Cell<isize>,
+}
+
+impl<T> Deref for DataRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> Drop for DataRef<'_, T> {
+    fn drop(&mut self) {
+        self.borrow.set(self.borrow.get() - 1);
+    }
+}
+
+/// SystemData 借出的可写引用
+pub struct DataMut<'a, T> {
+    value: &'a mut T,
+    borrow: &'a Cell<isize>,
+}
+
+impl<T> Deref for DataMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for DataMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<T> Drop for DataMut<'_, T> {
+    fn drop(&mut self) {
+        self.borrow.set(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppConfig, AppContext, SystemDescriptor, SystemDispatcher};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    struct Counter(u32);
+
+    impl IComponent for Counter {}
+
+    fn world_with_counter() -> World {
+        let mut world = World::new_with_default_registry();
+        world.register_component::<Counter>();
+        world
+    }
+
+    #[test]
+    fn access_compatibility() {
+        let read = SystemAccess::new().read::<Counter>();
+        let write = SystemAccess::new().write::<Counter>();
+
+        assert!(read.is_compatible(&read));
+        assert!(!read.is_compatible(&write));
+        assert!(!write.is_compatible(&read));
+        assert!(!write.is_compatible(&write));
+        assert!(
+            SystemAccess::new()
+                .write_resource::<u32>()
+                .is_compatible(&write.clone().write_resource::<u64>())
+        );
+    }
+
+    #[test]
+    fn second_writer_in_one_batch_can_not_borrow() {
+        let world = world_with_counter();
+        let write = SystemAccess::new().write::<Counter>();
+        let mut guards = Vec::new();
+
+        let _first = SystemData::borrow(&world, &write, 0, &mut guards).unwrap();
+        let second = SystemData::borrow(&world, &write, 0, &mut guards);
+        assert!(matches!(second, Err(QueryError::AlreadyBorrowed(_))));
+    }
+
+    #[test]
+    fn reader_and_writer_in_one_batch_can_not_alias() {
+        let world = world_with_counter();
+        let read = SystemAccess::new().read::<Counter>();
+        let write = SystemAccess::new().write::<Counter>();
+
+        let mut guards = Vec::new();
+        let _reader = SystemData::borrow(&world, &read, 0, &mut guards).unwrap();
+        let writer = SystemData::borrow(&world, &write, 0, &mut guards);
+        assert!(matches!(writer, Err(QueryError::AlreadyBorrowed(_))));
+        // 多个读者可以共存
+        assert!(SystemData::borrow(&world, &read, 0, &mut guards).is_ok());
+        drop(guards);
+
+        let mut guards = Vec::new();
+        let _writer = SystemData::borrow(&world, &write, 0, &mut guards).unwrap();
+        let reader = SystemData::borrow(&world, &read, 0, &mut guards);
+        assert!(matches!(reader, Err(QueryError::AlreadyBorrowed(_))));
+    }
+
+    #[test]
+    #[should_panic]
+    fn write_while_reading_in_one_system_panics() {
+        let world = world_with_counter();
+        let access = SystemAccess::new().write::<Counter>();
+        let mut guards = Vec::new();
+        let data = SystemData::borrow(&world, &access, 0, &mut guards).unwrap();
+
+        let _read = data.components::<Counter>();
+        let _write = data.components_mut::<Counter>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn write_without_declaration_panics() {
+        let world = world_with_counter();
+        let access = SystemAccess::new().read::<Counter>();
+        let mut guards = Vec::new();
+        let data = SystemData::borrow(&world, &access, 0, &mut guards).unwrap();
+
+        let _write = data.components_mut::<Counter>();
+    }
+
+    /// 记录同时运行的系统数量的最大值
+    struct Probe {
+        name: &'static str,
+        access: SystemAccess,
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl IParallelSystem for Probe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn access(&self) -> SystemAccess {
+            self.access.clone()
+        }
+
+        fn update(
+            &mut self,
+            _data: &SystemData<'_>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            // 等另一个系统开始运行，被串行执行时等到超时为止
+            let deadline = Instant::now() + Duration::from_millis(100);
+            while self.running.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            self.max_running
+                .fetch_max(self.running.load(Ordering::SeqCst), Ordering::SeqCst);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// 运行两个访问给定数据的并行系统，返回 (同时运行的最大数量, 运行次数)
+    fn run_pair(first: SystemAccess, second: SystemAccess) -> (usize, usize) {
+        // 单核机器上全局线程池只有一个线程，测试需要至少两个；只有这里的测试用到线程池
+        let _ = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build_global();
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .register_component::<Counter>();
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut dispatcher = SystemDispatcher::new();
+        for (name, access) in [("First", first), ("Second", second)] {
+            dispatcher.add_system(SystemDescriptor::parallel(Probe {
+                name,
+                access,
+                running: running.clone(),
+                max_running: max_running.clone(),
+                runs: runs.clone(),
+            }));
+        }
+
+        dispatcher.run_systems(app_context, 0.0).unwrap();
+        (
+            max_running.load(Ordering::SeqCst),
+            runs.load(Ordering::SeqCst),
+        )
+    }
+
+    #[test]
+    fn conflicting_writers_are_serialized() {
+        let write = SystemAccess::new().write::<Counter>();
+        assert_eq!(run_pair(write.clone(), write), (1, 2));
+    }
+
+    #[test]
+    fn reader_and_writer_are_serialized() {
+        let read = SystemAccess::new().read::<Counter>();
+        let write = SystemAccess::new().write::<Counter>();
+        assert_eq!(run_pair(read, write), (1, 2));
+    }
+
+    #[test]
+    fn compatible_readers_run_concurrently() {
+        let read = SystemAccess::new().read::<Counter>();
+        assert_eq!(run_pair(read.clone(), read), (2, 2));
+    }
+}
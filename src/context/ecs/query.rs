@@ -0,0 +1,640 @@
+use std::any::{TypeId, type_name};
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+
+use thiserror::Error;
+
+use super::{CellSlots, ComponentManager, EntityHandle, IComponent, World};
+
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("Component is not registered: {0}")]
+    NotRegistered(&'static str),
+    #[error("Conflicting access to component in one query: {0}")]
+    AccessConflict(&'static str),
+    #[error("Component manager is already borrowed: {0}")]
+    AlreadyBorrowed(&'static str),
+    #[error("Resource does not exist: {0}")]
+    ResourceNotFound(&'static str),
+}
+
+/// 组件访问方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// 一次查询声明的所有组件访问，用于提前检查别名冲突
+#[derive(Debug, Default)]
+pub struct QueryAccess {
+    items: Vec<(TypeId, &'static str, Access)>,
+}
+
+impl QueryAccess {
+    /// 声明只读访问
+    pub fn add_read<T: 'static>(&mut self) {
+        self.items
+            .push((TypeId::of::<T>(), type_name::<T>(), Access::Read));
+    }
+
+    /// 声明可写访问
+    pub fn add_write<T: 'static>(&mut self) {
+        self.items
+            .push((TypeId::of::<T>(), type_name::<T>(), Access::Write));
+    }
+
+    /// 同一类型出现多次且其中有写访问即为冲突
+    pub fn check(&self) -> Result<(), QueryError> {
+        for (i, (type_id, name, access)) in self.items.iter().enumerate() {
+            for (other_id, _, other_access) in &self.items[i + 1..] {
+                if type_id == other_id
+                    && (*access == Access::Write || *other_access == Access::Write)
+                {
+                    return Err(QueryError::AccessConflict(name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 可以被查询的数据：`&T`、`&mut T`、`Option<Q>` 以及它们的元组
+pub trait QueryData {
+    /// 从 World 借出的管理器
+    type Fetch<'w>;
+    /// 迭代时的中间存储
+    type Slots<'q>;
+    /// 单个实体的查询结果
+    type Item<'q>;
+    /// 带实体句柄的查询结果，元组会被展开为 `(EntityHandle, A, B, ...)`
+    type Row<'q>;
+
+    fn access(access: &mut QueryAccess);
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError>;
+    /// 必须拥有的组件中实体最少的一组，查询只需要遍历这些实体；`None` 表示没有限制
+    fn candidates<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [EntityHandle]>;
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool;
+    fn slots<'q>(fetch: &'q mut Self::Fetch<'_>) -> Self::Slots<'q>;
+    /// 从 slots 中取出实体的查询结果
+    ///
+    /// # Safety
+    ///
+    /// 同一个 slots 中每个实体最多只能取出一次。
+    unsafe fn take<'q>(slots: &mut Self::Slots<'q>, entity: EntityHandle)
+    -> Option<Self::Item<'q>>;
+    fn row(entity: EntityHandle, item: Self::Item<'_>) -> Self::Row<'_>;
+}
+
+impl<T: IComponent> QueryData for &T {
+    type Fetch<'w> = Ref<'w, ComponentManager<T>>;
+    type Slots<'q> = &'q ComponentManager<T>;
+    type Item<'q> = &'q T;
+    type Row<'q> = (EntityHandle, &'q T);
+
+    fn access(access: &mut QueryAccess) {
+        access.add_read::<T>();
+    }
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        world.try_get_manager::<T>()
+    }
+
+    fn candidates<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [EntityHandle]> {
+        Some(fetch.entities())
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        fetch.has(entity)
+    }
+
+    fn slots<'q>(fetch: &'q mut Self::Fetch<'_>) -> Self::Slots<'q> {
+        fetch
+    }
+
+    unsafe fn take<'q>(
+        slots: &mut Self::Slots<'q>,
+        entity: EntityHandle,
+    ) -> Option<Self::Item<'q>> {
+        slots.get(entity)
+    }
+
+    fn row(entity: EntityHandle, item: Self::Item<'_>) -> Self::Row<'_> {
+        (entity, item)
+    }
+}
+
+/// 只读的查询数据，可以通过共享引用遍历
+pub trait ReadOnlyQueryData: QueryData {
+    fn get<'q>(fetch: &'q Self::Fetch<'_>, entity: EntityHandle) -> Option<Self::Item<'q>>;
+}
+
+impl<T: IComponent> ReadOnlyQueryData for &T {
+    fn get<'q>(fetch: &'q Self::Fetch<'_>, entity: EntityHandle) -> Option<Self::Item<'q>> {
+        fetch.get(entity)
+    }
+}
+
+impl<T: IComponent> QueryData for &mut T {
+    type Fetch<'w> = RefMut<'w, ComponentManager<T>>;
+    type Slots<'q> = CellSlots<'q, T>;
+    type Item<'q> = &'q mut T;
+    type Row<'q> = (EntityHandle, &'q mut T);
+
+    fn access(access: &mut QueryAccess) {
+        access.add_write::<T>();
+    }
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        world.try_get_manager_mut::<T>()
+    }
+
+    fn candidates<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [EntityHandle]> {
+        Some(fetch.entities())
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        fetch.has(entity)
+    }
+
+    fn slots<'q>(fetch: &'q mut Self::Fetch<'_>) -> Self::Slots<'q> {
+        fetch.cell_slots()
+    }
+
+    unsafe fn take<'q>(
+        slots: &mut Self::Slots<'q>,
+        entity: EntityHandle,
+    ) -> Option<Self::Item<'q>> {
+        // 只有真正被取出的组件才标记为已修改
+        // SAFETY: 调用方保证每个实体只取出一次
+        unsafe { slots.take(entity) }
+    }
+
+    fn row(entity: EntityHandle, item: Self::Item<'_>) -> Self::Row<'_> {
+        (entity, item)
+    }
+}
+
+impl<Q: QueryData> QueryData for Option<Q> {
+    type Fetch<'w> = Q::Fetch<'w>;
+    type Slots<'q> = Q::Slots<'q>;
+    type Item<'q> = Option<Q::Item<'q>>;
+    type Row<'q> = (EntityHandle, Option<Q::Item<'q>>);
+
+    fn access(access: &mut QueryAccess) {
+        Q::access(access);
+    }
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        Q::fetch(world)
+    }
+
+    fn candidates<'a>(_fetch: &'a Self::Fetch<'_>) -> Option<&'a [EntityHandle]> {
+        None
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: EntityHandle) -> bool {
+        true
+    }
+
+    fn slots<'q>(fetch: &'q mut Self::Fetch<'_>) -> Self::Slots<'q> {
+        Q::slots(fetch)
+    }
+
+    unsafe fn take<'q>(
+        slots: &mut Self::Slots<'q>,
+        entity: EntityHandle,
+    ) -> Option<Self::Item<'q>> {
+        // SAFETY: 由调用方保证
+        Some(unsafe { Q::take(slots, entity) })
+    }
+
+    fn row(entity: EntityHandle, item: Self::Item<'_>) -> Self::Row<'_> {
+        (entity, item)
+    }
+}
+
+impl<Q: ReadOnlyQueryData> ReadOnlyQueryData for Option<Q> {
+    fn get<'q>(fetch: &'q Self::Fetch<'_>, entity: EntityHandle) -> Option<Self::Item<'q>> {
+        Some(Q::get(fetch, entity))
+    }
+}
+
+macro_rules! impl_query_data_for_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+            type Slots<'q> = ($($name::Slots<'q>,)+);
+            type Item<'q> = ($($name::Item<'q>,)+);
+            type Row<'q> = (EntityHandle, $($name::Item<'q>,)+);
+
+            fn access(access: &mut QueryAccess) {
+                $($name::access(access);)+
+            }
+
+            fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+                Ok(($($name::fetch(world)?,)+))
+            }
+
+            fn candidates<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [EntityHandle]> {
+                let ($($name,)+) = fetch;
+                let mut smallest: Option<&'a [EntityHandle]> = None;
+                $(
+                    if let Some(candidates) = $name::candidates($name) {
+                        if smallest.is_none_or(|s| candidates.len() < s.len()) {
+                            smallest = Some(candidates);
+                        }
+                    }
+                )+
+                smallest
+            }
+
+            fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+                let ($($name,)+) = fetch;
+                $($name::matches($name, entity))&&+
+            }
+
+            fn slots<'q>(fetch: &'q mut Self::Fetch<'_>) -> Self::Slots<'q> {
+                let ($($name,)+) = fetch;
+                ($($name::slots($name),)+)
+            }
+
+            unsafe fn take<'q>(
+                slots: &mut Self::Slots<'q>,
+                entity: EntityHandle,
+            ) -> Option<Self::Item<'q>> {
+                let ($($name,)+) = slots;
+                // SAFETY: 访问检查保证元组中的可变组件互不相同，其余由调用方保证
+                Some(($(unsafe { $name::take($name, entity) }?,)+))
+            }
+
+            fn row(entity: EntityHandle, item: Self::Item<'_>) -> Self::Row<'_> {
+                let ($($name,)+) = item;
+                (entity, $($name,)+)
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($name: ReadOnlyQueryData),+> ReadOnlyQueryData for ($($name,)+) {
+            fn get<'q>(fetch: &'q Self::Fetch<'_>, entity: EntityHandle) -> Option<Self::Item<'q>> {
+                let ($($name,)+) = fetch;
+                Some(($($name::get($name, entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_data_for_tuple!(A);
+impl_query_data_for_tuple!(A, B);
+impl_query_data_for_tuple!(A, B, C);
+impl_query_data_for_tuple!(A, B, C, D);
+impl_query_data_for_tuple!(A, B, C, D, E);
+impl_query_data_for_tuple!(A, B, C, D, E, F);
+impl_query_data_for_tuple!(A, B, C, D, E, F, G);
+impl_query_data_for_tuple!(A, B, C, D, E, F, G, H);
+
+/// 查询过滤器：只筛选实体，不产出数据
+///
+/// 创建查询时先用查询的数据确定候选实体，释放借用后再借出过滤器逐个检查候选实体，
+/// 所以可以和同一组件的 `&mut T` 一起使用。
+pub trait QueryFilter {
+    type Fetch<'w>;
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError>;
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool;
+}
+
+/// 要求实体拥有组件 T
+pub struct With<T>(PhantomData<T>);
+
+/// 要求实体没有组件 T
+pub struct Without<T>(PhantomData<T>);
+
+/// 要求组件 T 在当前系统上一次运行之后被添加
+pub struct Added<T>(PhantomData<T>);
+
+/// 要求组件 T 在当前系统上一次运行之后被修改（包括添加）
+pub struct Changed<T>(PhantomData<T>);
+
+/// 要求实体和它的所有祖先都处于激活状态
+pub struct Active;
+
+impl QueryFilter for () {
+    type Fetch<'w> = ();
+
+    fn fetch(_world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        Ok(())
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: EntityHandle) -> bool {
+        true
+    }
+}
+
+impl<T: IComponent> QueryFilter for With<T> {
+    type Fetch<'w> = Ref<'w, ComponentManager<T>>;
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        world.try_get_manager::<T>()
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        fetch.has(entity)
+    }
+}
+
+impl<T: IComponent> QueryFilter for Without<T> {
+    type Fetch<'w> = Ref<'w, ComponentManager<T>>;
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        world.try_get_manager::<T>()
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        !fetch.has(entity)
+    }
+}
+
+impl<T: IComponent> QueryFilter for Added<T> {
+    type Fetch<'w> = (Ref<'w, ComponentManager<T>>, u32);
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        Ok((world.try_get_manager::<T>()?, world.last_run_tick()))
+    }
+
+    fn matches((manager, last_run): &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        manager.is_added(entity, *last_run)
+    }
+}
+
+impl<T: IComponent> QueryFilter for Changed<T> {
+    type Fetch<'w> = (Ref<'w, ComponentManager<T>>, u32);
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        Ok((world.try_get_manager::<T>()?, world.last_run_tick()))
+    }
+
+    fn matches((manager, last_run): &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        manager.is_changed(entity, *last_run)
+    }
+}
+
+impl QueryFilter for Active {
+    type Fetch<'w> = &'w World;
+
+    fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+        Ok(world)
+    }
+
+    fn matches(world: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+        world.is_active_in_hierarchy(entity)
+    }
+}
+
+macro_rules! impl_query_filter_for_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: QueryFilter),+> QueryFilter for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+
+            fn fetch(world: &World) -> Result<Self::Fetch<'_>, QueryError> {
+                Ok(($($name::fetch(world)?,)+))
+            }
+
+            fn matches(fetch: &Self::Fetch<'_>, entity: EntityHandle) -> bool {
+                let ($($name,)+) = fetch;
+                $($name::matches($name, entity))&&+
+            }
+        }
+    };
+}
+
+impl_query_filter_for_tuple!(A);
+impl_query_filter_for_tuple!(A, B);
+impl_query_filter_for_tuple!(A, B, C);
+impl_query_filter_for_tuple!(A, B, C, D);
+
+/// 多组件联合查询，持有所涉及组件管理器的借用
+pub struct Query<'w, Q: QueryData, F: QueryFilter = ()> {
+    data: Q::Fetch<'w>,
+    entities: Vec<EntityHandle>,
+    _filter: PhantomData<F>,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Query<'w, Q, F> {
+    pub(crate) fn new(world: &'w World) -> Result<Self, QueryError> {
+        let mut access = QueryAccess::default();
+        Q::access(&mut access);
+        access.check()?;
+
+        // 从实体最少的组件开始遍历，没有必需组件时遍历所有实体
+        let mut entities: Vec<EntityHandle> = {
+            let data = Q::fetch(world)?;
+            let matches = |entity: &EntityHandle| Q::matches(&data, *entity);
+            match Q::candidates(&data) {
+                Some(candidates) => candidates.iter().copied().filter(matches).collect(),
+                None => world.entities().into_iter().filter(matches).collect(),
+            }
+        };
+        // 释放数据的借用后再逐个检查过滤条件，过滤器和数据可以访问同一组件
+        {
+            let filter = F::fetch(world)?;
+            entities.retain(|entity| F::matches(&filter, *entity));
+        }
+        let data = Q::fetch(world)?;
+
+        Ok(Self {
+            data,
+            entities,
+            _filter: PhantomData,
+        })
+    }
+
+    /// 匹配的实体数量
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// 是否没有匹配的实体
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// 匹配的所有实体
+    pub fn entities(&self) -> &[EntityHandle] {
+        &self.entities
+    }
+
+    /// 是否匹配某个实体
+    pub fn contains(&self, entity: EntityHandle) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    /// 只读地遍历所有匹配结果 `(EntityHandle, ...)`
+    pub fn iter(&self) -> impl Iterator<Item = Q::Row<'_>>
+    where
+        Q: ReadOnlyQueryData,
+    {
+        self.entities
+            .iter()
+            .filter_map(|&entity| Q::get(&self.data, entity).map(|item| Q::row(entity, item)))
+    }
+
+    /// 遍历所有匹配结果 `(EntityHandle, ...)`
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = Q::Row<'_>> {
+        let mut slots = Q::slots(&mut self.data);
+        // SAFETY: entities 中没有重复的实体，每个实体只取出一次
+        self.entities.iter().filter_map(move |&entity| {
+            unsafe { Q::take(&mut slots, entity) }.map(|item| Q::row(entity, item))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct A(u32);
+    struct B(u32);
+    struct C;
+
+    impl IComponent for A {}
+    impl IComponent for B {}
+    impl IComponent for C {}
+
+    /// e0: A；e1: A B；e2: A C；e3: A B C
+    fn world() -> (World, Vec<EntityHandle>) {
+        let mut world = World::new_with_default_registry();
+        world.register_component::<A>();
+        world.register_component::<B>();
+        world.register_component::<C>();
+        let entities: Vec<EntityHandle> = (0..4).map(|_| world.spawn_entity()).collect();
+        for (i, &entity) in entities.iter().enumerate() {
+            world.add_component(entity, A(i as u32));
+            if i % 2 == 1 {
+                world.add_component(entity, B(i as u32 * 10));
+            }
+            if i >= 2 {
+                world.add_component(entity, C);
+            }
+        }
+        (world, entities)
+    }
+
+    #[test]
+    fn read_and_write_of_same_component_conflict() {
+        let (world, _) = world();
+        assert!(matches!(
+            world.query::<(&A, &mut A)>(),
+            Err(QueryError::AccessConflict(_))
+        ));
+        assert!(matches!(
+            world.query::<(&mut A, Option<&A>)>(),
+            Err(QueryError::AccessConflict(_))
+        ));
+        // 多次只读不冲突
+        assert!(world.query::<(&A, &A)>().is_ok());
+    }
+
+    #[test]
+    fn unregistered_component_is_an_error() {
+        struct Unregistered;
+        impl IComponent for Unregistered {}
+
+        let (world, _) = world();
+        assert!(matches!(
+            world.query::<&Unregistered>(),
+            Err(QueryError::NotRegistered(_))
+        ));
+    }
+
+    #[test]
+    fn tuple_query_yields_entities_with_all_components() {
+        let (world, e) = world();
+        let query = world.query::<(&A, &B)>().unwrap();
+        // 从实体最少的 B 开始遍历，顺序与 B 的添加顺序一致
+        let rows: Vec<(EntityHandle, u32, u32)> = query
+            .iter()
+            .map(|(entity, a, b)| (entity, a.0, b.0))
+            .collect();
+        assert_eq!(rows, [(e[1], 1, 10), (e[3], 3, 30)]);
+
+        let query = world.query::<(&A, &B, &C)>().unwrap();
+        assert_eq!(query.entities(), [e[3]]);
+        assert_eq!(world.query::<&A>().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn optional_component_does_not_restrict_matches() {
+        let (world, e) = world();
+        let query = world.query::<(&A, Option<&B>)>().unwrap();
+        let rows: Vec<(EntityHandle, Option<u32>)> = query
+            .iter()
+            .map(|(entity, _, b)| (entity, b.map(|b| b.0)))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (e[0], None),
+                (e[1], Some(10)),
+                (e[2], None),
+                (e[3], Some(30))
+            ]
+        );
+    }
+
+    #[test]
+    fn with_and_without_filters() {
+        let (world, e) = world();
+        let with = world.query_filtered::<&A, With<C>>().unwrap();
+        assert_eq!(with.entities(), [e[2], e[3]]);
+        let without = world.query_filtered::<&A, Without<B>>().unwrap();
+        assert_eq!(without.entities(), [e[0], e[2]]);
+        let both = world.query_filtered::<&A, (With<C>, Without<B>)>().unwrap();
+        assert_eq!(both.entities(), [e[2]]);
+        assert!(both.contains(e[2]));
+        assert!(!both.contains(e[3]));
+    }
+
+    #[test]
+    fn filter_can_be_combined_with_mutable_access() {
+        let (world, e) = world();
+        let mut query = world.query_filtered::<&mut A, With<B>>().unwrap();
+        for (_, a) in query.iter_mut() {
+            a.0 += 100;
+        }
+        drop(query);
+        let values: Vec<u32> = world
+            .query::<&A>()
+            .unwrap()
+            .iter()
+            .map(|(_, a)| a.0)
+            .collect();
+        assert_eq!(values, [0, 101, 2, 103]);
+        assert_eq!(world.get_manager::<A>().get(e[1]).unwrap().0, 101);
+    }
+
+    #[test]
+    fn active_filter_skips_inactive_hierarchies() {
+        let (world, e) = world();
+        world.set_parent(e[1], e[0]).unwrap();
+        world.set_active(e[0], false);
+        world.set_active(e[2], false);
+        let query = world.query_filtered::<&A, Active>().unwrap();
+        assert_eq!(query.entities(), [e[3]]);
+    }
+
+    #[test]
+    fn change_filter_can_access_the_queried_component() {
+        let (world, e) = world();
+        world.set_last_run_tick(world.increment_change_tick());
+        world.get_manager_mut::<A>().get_mut(e[2]).unwrap().0 += 1;
+
+        let mut query = world.query_filtered::<&mut A, Changed<A>>().unwrap();
+        let changed: Vec<EntityHandle> = query.iter_mut().map(|(entity, _)| entity).collect();
+        assert_eq!(changed, [e[2]]);
+        drop(query);
+        assert!(world.query_filtered::<&A, Added<A>>().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use thiserror::Error;
+
+use crate::{AppContext, IParallelSystem, ISystem};
+
+#[derive(Error, Debug)]
+pub enum ScheduleError {
+    #[error("System ordering cycle in stage {stage:?}: {systems:?}")]
+    CycleDetected { stage: Stage, systems: Vec<String> },
+}
+
+/// 系统运行阶段，按声明顺序依次执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Stage {
+    First,
+    PreUpdate,
+    #[default]
+    Update,
+    PostUpdate,
+    Render,
+    Last,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::First,
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::Render,
+        Stage::Last,
+    ];
+}
+
+/// 运行条件，返回 false 时本次跳过该系统
+pub type RunCondition = Box<dyn Fn(&AppContext) -> bool>;
+
+/// 主线程系统或并行系统
+pub(crate) enum SystemKind {
+    Main(Box<dyn ISystem>),
+    Parallel(Box<dyn IParallelSystem>),
+}
+
+impl SystemKind {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            SystemKind::Main(system) => system.name(),
+            SystemKind::Parallel(system) => system.name(),
+        }
+    }
+}
+
+/// 系统及其调度配置
+///
+/// ```ignore
+/// app.add_system(
+///     SystemDescriptor::new(MySystem::default())
+///         .in_stage(Stage::Update)
+///         .after("ScriptSystem")
+///         .run_if(|ctx| ctx.world.borrow().has_resource::<MyState>()),
+/// );
+/// ```
+pub struct SystemDescriptor {
+    pub(crate) system: SystemKind,
+    pub(crate) stage: Stage,
+    pub(crate) before: Vec<String>,
+    pub(crate) after: Vec<String>,
+    pub(crate) run_conditions: Vec<RunCondition>,
+}
+
+impl SystemDescriptor {
+    pub fn new<S: ISystem + 'static>(system: S) -> Self {
+        Self::from_kind(SystemKind::Main(Box::new(system)))
+    }
+
+    /// 并行系统，同一阶段中相邻且访问不冲突的并行系统会在线程池中同时运行
+    pub fn parallel<S: IParallelSystem + 'static>(system: S) -> Self {
+        Self::from_kind(SystemKind::Parallel(Box::new(system)))
+    }
+
+    fn from_kind(system: SystemKind) -> Self {
+        Self {
+            system,
+            stage: Stage::default(),
+            before: Vec::new(),
+            after: Vec::new(),
+            run_conditions: Vec::new(),
+        }
+    }
+
+    /// 指定运行阶段，默认 Update
+    pub fn in_stage(mut self, stage: Stage) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// 在同阶段的指定系统之前运行
+    pub fn before(mut self, name: impl Into<String>) -> Self {
+        self.before.push(name.into());
+        self
+    }
+
+    /// 在同阶段的指定系统之后运行
+    pub fn after(mut self, name: impl Into<String>) -> Self {
+        self.after.push(name.into());
+        self
+    }
+
+    /// 添加运行条件，多个条件需同时满足
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&AppContext) -> bool + 'static,
+    {
+        self.run_conditions.push(Box::new(condition));
+        self
+    }
+}
+
+impl<S: ISystem + 'static> From<S> for SystemDescriptor {
+    fn from(system: S) -> Self {
+        Self::new(system)
+    }
+}
+
+pub(crate) enum SystemCommand {
+    SetEnabled(String, bool),
+    Remove(String),
+}
+
+/// 运行时控制系统的命令，作为资源存放在 World 中
+///
+/// 在系统或脚本中记录，调度器在下一次运行系统前执行。
+#[derive(Default)]
+pub struct SystemCommands {
+    queue: Vec<SystemCommand>,
+}
+
+impl SystemCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(&mut self, name: impl Into<String>) {
+        self.queue
+            .push(SystemCommand::SetEnabled(name.into(), true));
+    }
+
+    pub fn disable(&mut self, name: impl Into<String>) {
+        self.queue
+            .push(SystemCommand::SetEnabled(name.into(), false));
+    }
+
+    pub fn remove(&mut self, name: impl Into<String>) {
+        self.queue.push(SystemCommand::Remove(name.into()));
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<SystemCommand> {
+        std::mem::take(&mut self.queue)
+    }
+}
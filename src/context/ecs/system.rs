@@ -1,8 +1,15 @@
-use crate::AppContext;
+use crate::{
+    AppContext, ProfileCategory, Profiler, RunCondition, ScheduleError, Stage, SystemAccess,
+    SystemCommand, SystemCommands, SystemData, SystemDescriptor, SystemError, SystemErrorLog,
+    SystemErrorPolicy, SystemKind,
+};
+use log::{error, warn};
 use std::cell::RefCell;
 use std::error::Error;
 use std::rc::Rc;
+use std::time::Instant;
 
+/// 在主线程运行的系统，可以访问 `AppContext` 和 OpenGL
 pub trait ISystem {
     fn name(&self) -> &str;
     fn init(&mut self, _app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
@@ -22,52 +29,190 @@ pub trait ISystem {
     ) -> Result<(), Box<dyn Error>> {
         Ok(())
     }
+    /// 应用退出时按运行顺序的逆序调用，此时 World 和 OpenGL 上下文仍然可用
+    fn shutdown(&mut self, _app_context: Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RunMode {
+    Update,
+    FixedUpdate,
+}
+
+impl RunMode {
+    fn category(self) -> ProfileCategory {
+        match self {
+            RunMode::Update => ProfileCategory::Update,
+            RunMode::FixedUpdate => ProfileCategory::FixedUpdate,
+        }
+    }
+}
+
+/// 注册的系统及其运行记录
+pub(crate) struct SystemSlot {
+    pub(crate) system: SystemKind,
+    stage: Stage,
+    before: Vec<String>,
+    after: Vec<String>,
+    run_conditions: Vec<RunCondition>,
+    enabled: bool,
+    initialized: bool,
+    // 累计出错次数，重新启用时清零
+    error_count: u32,
+    // 上一次 update / fixed_update 运行的时刻，用于变化检测
+    last_run_tick: u32,
+    last_fixed_run_tick: u32,
+}
+
+impl SystemSlot {
+    fn should_run(&self, app_context: &Rc<RefCell<AppContext>>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let context = app_context.borrow();
+        self.run_conditions
+            .iter()
+            .all(|condition| condition(&context))
+    }
 }
 
 pub struct SystemDispatcher {
-    pub(crate) systems: Vec<Box<dyn ISystem>>,
+    pub(crate) systems: Vec<SystemSlot>,
+    // 按阶段和依赖排好序的系统下标
+    order: Vec<usize>,
+    is_dirty: bool,
 }
 
 impl SystemDispatcher {
     pub fn new() -> Self {
         Self {
             systems: Vec::new(),
+            order: Vec::new(),
+            is_dirty: false,
         }
     }
 
-    pub fn new_with_default_systems() -> Self {
-        let mut sd = Self::new();
+    // 注册系统，未初始化的系统会在下一次运行前初始化
+    pub fn add_system(&mut self, system: impl Into<SystemDescriptor>) {
+        let descriptor = system.into();
+        self.systems.push(SystemSlot {
+            system: descriptor.system,
+            stage: descriptor.stage,
+            before: descriptor.before,
+            after: descriptor.after,
+            run_conditions: descriptor.run_conditions,
+            enabled: true,
+            initialized: false,
+            error_count: 0,
+            last_run_tick: 0,
+            last_fixed_run_tick: 0,
+        });
+        self.is_dirty = true;
+    }
 
-        sd.add_system(super::CameraSystem::default());
-        sd.add_system(super::RenderSystem::default());
-        sd.add_system(super::ScriptSystem::default());
+    /// 移除所有同名系统，返回是否有系统被移除
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|slot| slot.system.name() != name);
+        self.is_dirty = true;
+        self.systems.len() != count
+    }
 
-        sd
+    /// 启用或禁用所有同名系统
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for slot in self.systems.iter_mut().filter(|s| s.system.name() == name) {
+            slot.enabled = enabled;
+            if enabled {
+                slot.error_count = 0;
+            }
+            found = true;
+        }
+        found
     }
 
-    // 注册系统
-    pub fn add_system<S: ISystem + 'static>(&mut self, system: S) {
-        self.systems.push(Box::new(system));
+    pub fn is_system_enabled(&self, name: &str) -> Option<bool> {
+        self.systems
+            .iter()
+            .find(|slot| slot.system.name() == name)
+            .map(|slot| slot.enabled)
+    }
+
+    /// 按运行顺序返回系统名称
+    pub fn system_names(&mut self) -> Result<Vec<String>, ScheduleError> {
+        self.rebuild_order()?;
+        Ok(self
+            .order
+            .iter()
+            .map(|&i| self.systems[i].system.name().to_string())
+            .collect())
     }
 
     pub(crate) fn init_systems(
         &mut self,
         app_context: Rc<RefCell<AppContext>>,
     ) -> Result<(), Box<dyn Error>> {
-        for system in &mut self.systems {
-            system.init(app_context.clone())?;
+        self.rebuild_order()?;
+        for &i in &self.order {
+            let slot = &mut self.systems[i];
+            if slot.initialized {
+                continue;
+            }
+            slot.initialized = true;
+            let SystemKind::Main(system) = &mut slot.system else {
+                continue;
+            };
+            let result = system.init(app_context.clone());
+            Self::apply_commands(&app_context);
+            if let Err(e) = result {
+                Self::handle_error(slot, &app_context, e)?;
+            }
         }
         Ok(())
     }
 
+    /// 按运行顺序的逆序关闭已初始化的系统，出错时记录日志并继续关闭其余系统
+    pub(crate) fn shutdown_systems(&mut self, app_context: Rc<RefCell<AppContext>>) {
+        // 排序失败时按注册顺序关闭
+        let order = match self.rebuild_order() {
+            Ok(()) => self.order.clone(),
+            Err(e) => {
+                error!("shutdown system error: {}", e);
+                (0..self.systems.len()).collect()
+            }
+        };
+        for &i in order.iter().rev() {
+            let slot = &mut self.systems[i];
+            if !slot.initialized {
+                continue;
+            }
+            slot.initialized = false;
+            let SystemKind::Main(system) = &mut slot.system else {
+                continue;
+            };
+            let result = system.shutdown(app_context.clone());
+            Self::apply_commands(&app_context);
+            if let Err(e) = result {
+                error!("system {} shutdown error: {}", system.name(), e);
+            }
+        }
+    }
+
     pub(crate) fn run_systems(
         &mut self,
         app_context: Rc<RefCell<AppContext>>,
         delta_dt: f32,
     ) -> Result<(), Box<dyn Error>> {
-        for system in &mut self.systems {
-            system.update(app_context.clone(), delta_dt)?;
-        }
+        self.prepare(&app_context)?;
+        self.run_ordered(&app_context, delta_dt, RunMode::Update)?;
+
+        // 一帧结束，清理过期的删除记录和事件
+        let context = app_context.borrow();
+        let mut world = context.world.borrow_mut();
+        world.clear_trackers();
+        world.update_events();
         Ok(())
     }
 
@@ -76,9 +221,764 @@ impl SystemDispatcher {
         app_context: Rc<RefCell<AppContext>>,
         delta_dt: f32,
     ) -> Result<(), Box<dyn Error>> {
-        for system in &mut self.systems {
-            system.fixed_update(app_context.clone(), delta_dt)?;
+        self.prepare(&app_context)?;
+        self.run_ordered(&app_context, delta_dt, RunMode::FixedUpdate)?;
+        Ok(())
+    }
+
+    /// 按顺序运行系统，相邻的并行系统合并为批次
+    fn run_ordered(
+        &mut self,
+        app_context: &Rc<RefCell<AppContext>>,
+        delta_dt: f32,
+        mode: RunMode,
+    ) -> Result<(), SystemError> {
+        let order = self.order.clone();
+        let mut k = 0;
+        while k < order.len() {
+            let i = order[k];
+            k += 1;
+            if !self.systems[i].should_run(app_context) {
+                continue;
+            }
+
+            let SystemKind::Parallel(system) = &self.systems[i].system else {
+                self.run_main_system(i, app_context, delta_dt, mode)?;
+                continue;
+            };
+
+            // 向后收集可以同时运行的并行系统
+            let mut batch = vec![(i, system.access())];
+            while k < order.len() {
+                let next = &self.systems[order[k]];
+                let SystemKind::Parallel(next_system) = &next.system else {
+                    break;
+                };
+                if next.stage != self.systems[i].stage {
+                    break;
+                }
+                if !next.should_run(app_context) {
+                    k += 1;
+                    continue;
+                }
+                let access = next_system.access();
+                let conflicts = batch.iter().any(|(j, other)| {
+                    !access.is_compatible(other) || self.has_ordering(order[k], *j)
+                });
+                if conflicts {
+                    break;
+                }
+                batch.push((order[k], access));
+                k += 1;
+            }
+
+            self.run_parallel_batch(batch, app_context, delta_dt, mode)?;
         }
         Ok(())
     }
+
+    fn run_main_system(
+        &mut self,
+        index: usize,
+        app_context: &Rc<RefCell<AppContext>>,
+        delta_dt: f32,
+        mode: RunMode,
+    ) -> Result<(), SystemError> {
+        let slot = &mut self.systems[index];
+        let SystemKind::Main(system) = &mut slot.system else {
+            return Ok(());
+        };
+
+        let profile_start = Self::profile_now(app_context);
+        let result = match mode {
+            RunMode::Update => {
+                Self::begin_system(app_context, slot.last_run_tick);
+                let result = system.update(app_context.clone(), delta_dt);
+                slot.last_run_tick = Self::end_system(app_context);
+                result
+            }
+            RunMode::FixedUpdate => {
+                Self::begin_system(app_context, slot.last_fixed_run_tick);
+                let result = system.fixed_update(app_context.clone(), delta_dt);
+                slot.last_fixed_run_tick = Self::end_system(app_context);
+                result
+            }
+        };
+        if let Some(start) = profile_start {
+            Self::record_profile(app_context, system.name(), mode, 0, start, None);
+        }
+
+        if let Err(e) = result {
+            Self::handle_error(slot, app_context, e)?;
+        }
+        Ok(())
+    }
+
+    /// 在主线程借出批次需要的数据，然后在线程池中同时运行
+    fn run_parallel_batch(
+        &mut self,
+        batch: Vec<(usize, SystemAccess)>,
+        app_context: &Rc<RefCell<AppContext>>,
+        delta_dt: f32,
+        mode: RunMode,
+    ) -> Result<(), SystemError> {
+        let mut errors: Vec<(usize, Box<dyn Error>)> = Vec::new();
+        let profile_start = Self::profile_now(app_context);
+        // (下标, 线程, 相对批次开始的时间, 耗时)
+        let mut timings = Vec::new();
+        // 真正运行过的系统，借用失败的系统不推进时刻
+        let mut ran = Vec::new();
+        {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            let mut guards = Vec::new();
+
+            let mut jobs = Vec::new();
+            for (index, access) in &batch {
+                let slot = &self.systems[*index];
+                let last_run_tick = match mode {
+                    RunMode::Update => slot.last_run_tick,
+                    RunMode::FixedUpdate => slot.last_fixed_run_tick,
+                };
+                match SystemData::borrow(&world, access, last_run_tick, &mut guards) {
+                    Ok(data) => jobs.push((*index, data)),
+                    Err(e) => errors.push((*index, Box::new(e))),
+                }
+            }
+
+            // 每个任务独占自己的系统和数据
+            let mut runs: Vec<_> = self
+                .systems
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(index, slot)| {
+                    let job = jobs.iter().position(|(i, _)| *i == index)?;
+                    let (_, data) = jobs.swap_remove(job);
+                    match &mut slot.system {
+                        SystemKind::Parallel(system) => Some((index, system, data, None, None)),
+                        SystemKind::Main(_) => None,
+                    }
+                })
+                .collect();
+
+            // App 的时钟不能跨线程使用，工作线程用 Instant 测量相对批次开始的偏移和耗时
+            let batch_start = Instant::now();
+            rayon::scope(|scope| {
+                for (_, system, data, result, timing) in runs.iter_mut() {
+                    scope.spawn(move |_| {
+                        let start = Instant::now();
+                        *result = Some(match mode {
+                            RunMode::Update => system.update(data, delta_dt),
+                            RunMode::FixedUpdate => system.fixed_update(data, delta_dt),
+                        });
+                        *timing = Some((
+                            rayon::current_thread_index().map_or(0, |i| i + 1),
+                            start.duration_since(batch_start).as_secs_f64(),
+                            start.elapsed().as_secs_f64(),
+                        ));
+                    });
+                }
+            });
+
+            for (index, _, _, result, timing) in runs {
+                ran.push(index);
+                if let Some(Err(e)) = result {
+                    errors.push((index, e));
+                }
+                if let Some(timing) = timing {
+                    timings.push((index, timing));
+                }
+            }
+        }
+
+        if let Some(start) = profile_start {
+            for (index, (thread, offset, duration)) in timings {
+                let name = self.systems[index].system.name();
+                Self::record_profile(
+                    app_context,
+                    name,
+                    mode,
+                    thread,
+                    start + offset,
+                    Some(duration),
+                );
+            }
+        }
+
+        let tick = Self::end_system(app_context);
+        for index in ran {
+            let slot = &mut self.systems[index];
+            match mode {
+                RunMode::Update => slot.last_run_tick = tick,
+                RunMode::FixedUpdate => slot.last_fixed_run_tick = tick,
+            }
+        }
+
+        for (index, e) in errors {
+            Self::handle_error(&mut self.systems[index], app_context, e)?;
+        }
+        Ok(())
+    }
+
+    /// 两个系统之间是否有 before / after 约束
+    fn has_ordering(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (&self.systems[a], &self.systems[b]);
+        let (name_a, name_b) = (a.system.name(), b.system.name());
+        a.before.iter().chain(a.after.iter()).any(|n| n == name_b)
+            || b.before.iter().chain(b.after.iter()).any(|n| n == name_a)
+    }
+
+    /// 执行运行时的系统命令，并初始化新加入的系统
+    fn prepare(&mut self, app_context: &Rc<RefCell<AppContext>>) -> Result<(), Box<dyn Error>> {
+        let commands = {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            world
+                .get_resource_mut::<SystemCommands>()
+                .map(|mut commands| commands.take())
+                .unwrap_or_default()
+        };
+
+        for command in commands {
+            match command {
+                SystemCommand::SetEnabled(name, enabled) => {
+                    if !self.set_system_enabled(&name, enabled) {
+                        warn!("system not found: {}", name);
+                    }
+                }
+                SystemCommand::Remove(name) => {
+                    if !self.remove_system(&name) {
+                        warn!("system not found: {}", name);
+                    }
+                }
+            }
+        }
+
+        if self.systems.iter().any(|slot| !slot.initialized) {
+            self.init_systems(app_context.clone())?;
+        }
+        self.rebuild_order()?;
+        Ok(())
+    }
+
+    /// 按阶段排序，同一阶段内根据 before / after 做拓扑排序，无约束时保持注册顺序
+    fn rebuild_order(&mut self) -> Result<(), ScheduleError> {
+        if !self.is_dirty {
+            return Ok(());
+        }
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        for stage in Stage::ALL {
+            let members: Vec<usize> = (0..self.systems.len())
+                .filter(|&i| self.systems[i].stage == stage)
+                .collect();
+
+            // edges[a] 包含 b 表示 a 必须先于 b 运行
+            let mut edges: Vec<Vec<usize>> = vec![Vec::new(); members.len()];
+            let mut in_degree = vec![0usize; members.len()];
+            for (a, &ia) in members.iter().enumerate() {
+                let slot = &self.systems[ia];
+                for (b, &ib) in members.iter().enumerate() {
+                    let other = self.systems[ib].system.name();
+                    if a == b {
+                        continue;
+                    }
+                    if slot.before.iter().any(|n| n == other) {
+                        edges[a].push(b);
+                    }
+                    if slot.after.iter().any(|n| n == other) {
+                        edges[b].push(a);
+                    }
+                }
+            }
+            for targets in &mut edges {
+                targets.sort_unstable();
+                targets.dedup();
+                for &t in targets.iter() {
+                    in_degree[t] += 1;
+                }
+            }
+
+            let mut done = vec![false; members.len()];
+            for _ in 0..members.len() {
+                // 每次取注册顺序最靠前的可运行系统，保证结果稳定
+                let Some(next) = (0..members.len()).find(|&k| !done[k] && in_degree[k] == 0) else {
+                    let systems = (0..members.len())
+                        .filter(|&k| !done[k])
+                        .map(|k| self.systems[members[k]].system.name().to_string())
+                        .collect();
+                    return Err(ScheduleError::CycleDetected { stage, systems });
+                };
+                done[next] = true;
+                for &t in &edges[next] {
+                    in_degree[t] -= 1;
+                }
+                order.push(members[next]);
+            }
+        }
+
+        self.order = order;
+        self.is_dirty = false;
+        Ok(())
+    }
+
+    /// 记录系统错误并按配置的策略处理，只有策略为 StopApp 时返回错误
+    fn handle_error(
+        slot: &mut SystemSlot,
+        app_context: &Rc<RefCell<AppContext>>,
+        err: Box<dyn Error>,
+    ) -> Result<(), SystemError> {
+        let name = slot.system.name().to_string();
+        let message = err.to_string();
+
+        let context = app_context.borrow();
+        let policy = context.app_config.borrow().system_error_policy;
+        let is_new = context
+            .world
+            .borrow()
+            .get_resource_mut::<SystemErrorLog>()
+            .is_none_or(|mut log| log.record(&name, &message));
+        // 相同的错误只输出一次，次数记录在 SystemErrorLog 中
+        if is_new {
+            error!("system {} error: {}", name, message);
+        }
+
+        slot.error_count += 1;
+        match policy {
+            SystemErrorPolicy::Continue => Ok(()),
+            SystemErrorPolicy::DisableAfter(max_errors) => {
+                if slot.error_count >= max_errors {
+                    slot.enabled = false;
+                    warn!("system {} disabled after {} errors", name, slot.error_count);
+                }
+                Ok(())
+            }
+            SystemErrorPolicy::StopApp => Err(SystemError::StopRequested {
+                system: name,
+                message,
+            }),
+        }
+    }
+
+    /// 告诉 World 当前系统上一次运行的时刻
+    fn begin_system(app_context: &Rc<RefCell<AppContext>>, last_run_tick: u32) {
+        app_context
+            .borrow()
+            .world
+            .borrow()
+            .set_last_run_tick(last_run_tick);
+    }
+
+    /// 同步点：执行积压的命令并推进时刻，返回本次运行的时刻
+    fn end_system(app_context: &Rc<RefCell<AppContext>>) -> u32 {
+        Self::apply_commands(app_context);
+        app_context.borrow().world.borrow().increment_change_tick()
+    }
+
+    /// Profiler 启用时返回当前时刻
+    fn profile_now(app_context: &Rc<RefCell<AppContext>>) -> Option<f64> {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        let profiler = world.get_resource::<Profiler>()?;
+        profiler.is_enabled().then(|| profiler.now())
+    }
+
+    /// 记录系统耗时，未指定耗时时计算到当前时刻
+    fn record_profile(
+        app_context: &Rc<RefCell<AppContext>>,
+        name: &str,
+        mode: RunMode,
+        thread: usize,
+        start: f64,
+        duration: Option<f64>,
+    ) {
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        if let Some(mut profiler) = world.get_resource_mut::<Profiler>() {
+            let duration = duration.unwrap_or_else(|| profiler.now() - start);
+            profiler.record_cpu(name, mode.category(), thread, start, duration);
+        }
+    }
+
+    /// 同步点：每个系统运行结束后执行积压的命令
+    fn apply_commands(app_context: &Rc<RefCell<AppContext>>) {
+        app_context.borrow().world.borrow_mut().apply_commands();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Added, AppConfig, IComponent};
+
+    /// 记录运行顺序的测试系统
+    struct Named {
+        name: &'static str,
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl ISystem for Named {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn update(
+            &mut self,
+            _app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            self.log.borrow_mut().push(self.name);
+            Ok(())
+        }
+    }
+
+    fn named(name: &'static str) -> Named {
+        named_with_log(name, &Rc::default())
+    }
+
+    fn named_with_log(name: &'static str, log: &Rc<RefCell<Vec<&'static str>>>) -> Named {
+        Named {
+            name,
+            log: log.clone(),
+        }
+    }
+
+    #[test]
+    fn keeps_registration_order_without_constraints() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(named("A"));
+        dispatcher.add_system(named("B"));
+        dispatcher.add_system(named("C"));
+
+        assert_eq!(dispatcher.system_names().unwrap(), ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn orders_by_stage_before_registration() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(SystemDescriptor::new(named("Last")).in_stage(Stage::Last));
+        dispatcher.add_system(named("Update"));
+        dispatcher.add_system(SystemDescriptor::new(named("First")).in_stage(Stage::First));
+        dispatcher.add_system(SystemDescriptor::new(named("Render")).in_stage(Stage::Render));
+
+        assert_eq!(
+            dispatcher.system_names().unwrap(),
+            ["First", "Update", "Render", "Last"]
+        );
+    }
+
+    #[test]
+    fn respects_before_and_after() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(SystemDescriptor::new(named("A")).after("C"));
+        dispatcher.add_system(named("B"));
+        dispatcher.add_system(SystemDescriptor::new(named("C")).after("B"));
+        dispatcher.add_system(SystemDescriptor::new(named("D")).before("B"));
+
+        assert_eq!(dispatcher.system_names().unwrap(), ["D", "B", "C", "A"]);
+    }
+
+    #[test]
+    fn ignores_constraints_across_stages() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(SystemDescriptor::new(named("A")).after("B"));
+        dispatcher.add_system(SystemDescriptor::new(named("B")).in_stage(Stage::PostUpdate));
+
+        assert_eq!(dispatcher.system_names().unwrap(), ["A", "B"]);
+    }
+
+    #[test]
+    fn ignores_missing_labels() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(SystemDescriptor::new(named("A")).after("Missing"));
+        dispatcher.add_system(SystemDescriptor::new(named("B")).before("Missing"));
+
+        assert_eq!(dispatcher.system_names().unwrap(), ["A", "B"]);
+    }
+
+    #[test]
+    fn reports_cycle_with_stage_and_members() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(named("Free"));
+        dispatcher.add_system(SystemDescriptor::new(named("A")).after("B"));
+        dispatcher.add_system(SystemDescriptor::new(named("B")).after("A"));
+
+        let Err(ScheduleError::CycleDetected { stage, systems }) = dispatcher.system_names() else {
+            panic!("cycle was not detected");
+        };
+        assert_eq!(stage, Stage::Update);
+        assert_eq!(systems, ["A", "B"]);
+    }
+
+    #[test]
+    fn rebuilds_order_after_removal() {
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(SystemDescriptor::new(named("A")).after("B"));
+        dispatcher.add_system(SystemDescriptor::new(named("B")).after("A"));
+        assert!(dispatcher.system_names().is_err());
+
+        assert!(dispatcher.remove_system("B"));
+        assert_eq!(dispatcher.system_names().unwrap(), ["A"]);
+    }
+
+    #[test]
+    fn skips_systems_whose_run_condition_fails() {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        let log = Rc::default();
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(named_with_log("Always", &log));
+        dispatcher
+            .add_system(SystemDescriptor::new(named_with_log("Never", &log)).run_if(|_| false));
+        dispatcher.add_system(
+            SystemDescriptor::new(named_with_log("WithResource", &log))
+                .run_if(|ctx| ctx.world.borrow().has_resource::<u32>()),
+        );
+
+        dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        assert_eq!(*log.borrow(), ["Always"]);
+
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .insert_resource(0u32);
+        dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        assert_eq!(*log.borrow(), ["Always", "Always", "WithResource"]);
+    }
+
+    #[test]
+    fn disabled_systems_do_not_run() {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        let log = Rc::default();
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(named_with_log("A", &log));
+        dispatcher.add_system(named_with_log("B", &log));
+
+        assert!(dispatcher.set_system_enabled("A", false));
+        dispatcher.run_systems(app_context, 0.0).unwrap();
+        assert_eq!(*log.borrow(), ["B"]);
+        assert_eq!(dispatcher.is_system_enabled("A"), Some(false));
+    }
+
+    struct Marker;
+
+    impl IComponent for Marker {}
+
+    /// 第一次运行时生成一个带 Marker 的实体
+    struct Spawner;
+
+    impl ISystem for Spawner {
+        fn name(&self) -> &str {
+            "Spawner"
+        }
+
+        fn update(
+            &mut self,
+            app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            if world.query::<&Marker>()?.is_empty() {
+                world.commands().spawn(Marker);
+            }
+            Ok(())
+        }
+    }
+
+    /// 记录每次运行时看到的新增 Marker 数量
+    struct AddedWatcher {
+        name: &'static str,
+        seen: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl ISystem for AddedWatcher {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn update(
+            &mut self,
+            app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            let context = app_context.borrow();
+            let world = context.world.borrow();
+            let added = world.query_filtered::<&Marker, Added<Marker>>()?.len();
+            self.seen.borrow_mut().push(added);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn each_system_sees_changes_since_its_own_last_run() {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        app_context
+            .borrow()
+            .world
+            .borrow_mut()
+            .register_component::<Marker>();
+        let early = Rc::new(RefCell::new(Vec::new()));
+        let late = Rc::new(RefCell::new(Vec::new()));
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(
+            SystemDescriptor::new(AddedWatcher {
+                name: "Early",
+                seen: early.clone(),
+            })
+            .before("Spawner"),
+        );
+        dispatcher.add_system(Spawner);
+        dispatcher.add_system(
+            SystemDescriptor::new(AddedWatcher {
+                name: "Late",
+                seen: late.clone(),
+            })
+            .after("Spawner"),
+        );
+
+        for _ in 0..3 {
+            dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        }
+        // Early 在生成之后的下一次运行才看到新增
+        assert_eq!(*early.borrow(), [0, 1, 0]);
+        assert_eq!(*late.borrow(), [1, 0, 0]);
+    }
+
+    /// 每次运行都失败，错误信息依次取自 messages
+    struct Failing {
+        name: &'static str,
+        messages: &'static [&'static str],
+        runs: Rc<RefCell<usize>>,
+    }
+
+    impl ISystem for Failing {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn update(
+            &mut self,
+            _app_context: Rc<RefCell<AppContext>>,
+            _delta_dt: f32,
+        ) -> Result<(), Box<dyn Error>> {
+            let mut runs = self.runs.borrow_mut();
+            let message = self.messages[*runs % self.messages.len()];
+            *runs += 1;
+            Err(message.into())
+        }
+    }
+
+    fn failing(
+        name: &'static str,
+        messages: &'static [&'static str],
+    ) -> (Failing, Rc<RefCell<usize>>) {
+        let runs = Rc::new(RefCell::new(0));
+        let system = Failing {
+            name,
+            messages,
+            runs: runs.clone(),
+        };
+        (system, runs)
+    }
+
+    fn context_with_policy(policy: SystemErrorPolicy) -> Rc<RefCell<AppContext>> {
+        let app_context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+        app_context
+            .borrow()
+            .app_config
+            .borrow_mut()
+            .system_error_policy = policy;
+        app_context
+    }
+
+    #[test]
+    fn continue_policy_keeps_failing_system_running() {
+        let app_context = context_with_policy(SystemErrorPolicy::Continue);
+        let log = Rc::default();
+        let (system, runs) = failing("Failing", &["boom"]);
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(system);
+        dispatcher.add_system(named_with_log("After", &log));
+
+        for _ in 0..3 {
+            dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        }
+        assert_eq!(*runs.borrow(), 3);
+        assert_eq!(log.borrow().len(), 3);
+        assert_eq!(dispatcher.is_system_enabled("Failing"), Some(true));
+    }
+
+    #[test]
+    fn disable_after_policy_disables_on_nth_error() {
+        let app_context = context_with_policy(SystemErrorPolicy::DisableAfter(3));
+        let (system, runs) = failing("Failing", &["boom"]);
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(system);
+
+        for _ in 0..2 {
+            dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        }
+        assert_eq!(dispatcher.is_system_enabled("Failing"), Some(true));
+
+        dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        assert_eq!(dispatcher.is_system_enabled("Failing"), Some(false));
+
+        dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        assert_eq!(*runs.borrow(), 3);
+    }
+
+    #[test]
+    fn stop_app_policy_returns_error_and_skips_remaining_systems() {
+        let app_context = context_with_policy(SystemErrorPolicy::StopApp);
+        let log = Rc::default();
+        let (system, _) = failing("Failing", &["boom"]);
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(system);
+        dispatcher.add_system(named_with_log("After", &log));
+
+        let err = dispatcher.run_systems(app_context, 0.0).unwrap_err();
+        let Some(SystemError::StopRequested { system, message }) = err.downcast_ref() else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!(system, "Failing");
+        assert_eq!(message, "boom");
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn error_log_deduplicates_by_system_and_message() {
+        let app_context = context_with_policy(SystemErrorPolicy::Continue);
+        let (first, _) = failing("First", &["boom", "boom", "bang"]);
+        let (second, _) = failing("Second", &["boom"]);
+        let mut dispatcher = SystemDispatcher::new();
+        dispatcher.add_system(first);
+        dispatcher.add_system(second);
+
+        for _ in 0..3 {
+            dispatcher.run_systems(app_context.clone(), 0.0).unwrap();
+        }
+        let context = app_context.borrow();
+        let world = context.world.borrow();
+        let error_log = world.resource::<SystemErrorLog>();
+        let entries: Vec<(&str, &str, u32)> = error_log
+            .entries()
+            .iter()
+            .map(|entry| {
+                (
+                    entry.system_name.as_str(),
+                    entry.message.as_str(),
+                    entry.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("First", "boom", 2),
+                ("Second", "boom", 3),
+                ("First", "bang", 1)
+            ]
+        );
+    }
 }
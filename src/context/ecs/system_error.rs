@@ -0,0 +1,89 @@
+use chrono::{DateTime, Local};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SystemError {
+    #[error("System {system} failed and stopped the app: {message}")]
+    StopRequested { system: String, message: String },
+}
+
+/// 系统出错时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SystemErrorPolicy {
+    /// 记录错误，其余系统继续运行
+    #[default]
+    Continue,
+    /// 同一系统累计出错 N 次后禁用该系统
+    DisableAfter(u32),
+    /// 停止整个应用
+    StopApp,
+}
+
+/// 一条去重后的系统错误记录
+#[derive(Debug, Clone)]
+pub struct SystemErrorEntry {
+    pub system_name: String,
+    pub message: String,
+    pub count: u32,
+    pub first_time: DateTime<Local>,
+    pub last_time: DateTime<Local>,
+}
+
+/// 系统错误日志，作为资源存放在 World 中
+///
+/// 相同系统的相同错误信息只保留一条，通过 `count` 累计次数。
+#[derive(Debug, Default)]
+pub struct SystemErrorLog {
+    entries: Vec<SystemErrorEntry>,
+}
+
+impl SystemErrorLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录错误，返回该错误是否首次出现
+    pub fn record(&mut self, system_name: &str, message: &str) -> bool {
+        let now = Local::now();
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.system_name == system_name && e.message == message)
+        {
+            entry.count += 1;
+            entry.last_time = now;
+            return false;
+        }
+
+        self.entries.push(SystemErrorEntry {
+            system_name: system_name.to_string(),
+            message: message.to_string(),
+            count: 1,
+            first_time: now,
+            last_time: now,
+        });
+        true
+    }
+
+    pub fn entries(&self) -> &[SystemErrorEntry] {
+        &self.entries
+    }
+
+    /// 指定系统的错误记录
+    pub fn entries_for<'a>(
+        &'a self,
+        system_name: &'a str,
+    ) -> impl Iterator<Item = &'a SystemErrorEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| e.system_name == system_name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -1,7 +1,12 @@
+use super::ComponentBundle;
 use super::ComponentManager;
 use super::IComponentManager;
-use crate::IComponent;
-use std::any::TypeId;
+use super::{CommandQueue, Commands};
+use super::{EventWriter, Events};
+use super::{Query, QueryData, QueryError, QueryFilter};
+use crate::{Children, HierarchyError, IComponent, Parent};
+use std::any::{Any, TypeId, type_name};
+use std::cell::Cell;
 use std::cell::Ref;
 use std::cell::RefCell;
 use std::cell::RefMut;
@@ -13,33 +18,60 @@ new_key_type! {
     pub struct EntityHandle;
 }
 
+// 实体槽位上的状态
+struct EntityMeta {
+    active: bool,
+}
+
+impl Default for EntityMeta {
+    fn default() -> Self {
+        Self { active: true }
+    }
+}
+
 pub struct World {
     components: HashMap<TypeId, RefCell<Box<dyn IComponentManager>>>,
-    entities: SlotMap<EntityHandle, ()>,
+    entities: RefCell<SlotMap<EntityHandle, EntityMeta>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    // 每帧结束时交换各事件通道的缓冲
+    event_updaters: Vec<fn(&World)>,
+    command_queue: RefCell<CommandQueue>,
+
+    // 变化检测
+    change_tick: Cell<u32>,
+    last_run_tick: Cell<u32>,
+    last_clear_tick: u32,
 }
 
 impl World {
+    /// 注册变换和层级组件，其他内置组件由插件注册
     pub fn new_with_default_registry() -> Self {
         let mut result = Self {
             components: HashMap::new(),
-            entities: SlotMap::with_key(),
+            entities: RefCell::new(SlotMap::with_key()),
+            resources: HashMap::new(),
+            event_updaters: Vec::new(),
+            command_queue: RefCell::new(CommandQueue::default()),
+            change_tick: Cell::new(1),
+            last_run_tick: Cell::new(0),
+            last_clear_tick: 0,
         };
 
         result.register_component::<crate::Transform>();
-        result.register_component::<crate::Light>();
-        result.register_component::<crate::Camera>();
-        result.register_component::<crate::Renderable>();
-        result.register_component::<crate::Scriptable>();
+        result.register_component::<crate::GlobalTransform>();
+        result.register_component::<crate::TransformInterpolation>();
+        result.register_component::<crate::Parent>();
+        result.register_component::<crate::Children>();
 
         result
     }
 
-    /// 手动注册组件
+    /// 手动注册组件，已注册的组件保持不变
     pub fn register_component<T: IComponent + 'static>(&mut self) {
         let type_id = TypeId::of::<T>();
-        let manager = ComponentManager::<T>::new();
         self.components
-            .insert(type_id, RefCell::new(Box::new(manager)));
+            .entry(type_id)
+            .or_insert_with(|| RefCell::new(Box::new(ComponentManager::<T>::new())));
     }
 
     /// 获取只读管理器
@@ -66,7 +98,9 @@ impl World {
         let cell_ref = self.components.get(&type_id).expect("组件未注册");
 
         // 使用 RefMut::map 将 Box<dyn IComponentManager> 映射为具体的 ComponentManager<T>
+        let tick = self.change_tick.get();
         RefMut::map(cell_ref.borrow_mut(), |manager_box| {
+            manager_box.set_change_tick(tick);
             manager_box
                 .as_any_mut()
                 .downcast_mut::<ComponentManager<T>>()
@@ -74,21 +108,197 @@ impl World {
         })
     }
 
+    /// 尝试获取只读管理器，未注册或已被可变借用时返回错误
+    pub fn try_get_manager<'a, T: IComponent + 'static>(
+        &'a self,
+    ) -> Result<Ref<'a, ComponentManager<T>>, QueryError> {
+        let type_id = TypeId::of::<T>();
+
+        let cell_ref = self
+            .components
+            .get(&type_id)
+            .ok_or(QueryError::NotRegistered(type_name::<T>()))?;
+
+        let manager = cell_ref
+            .try_borrow()
+            .map_err(|_| QueryError::AlreadyBorrowed(type_name::<T>()))?;
+
+        Ok(Ref::map(manager, |manager_box| {
+            manager_box
+                .as_any()
+                .downcast_ref::<ComponentManager<T>>()
+                .expect("类型转换失败")
+        }))
+    }
+
+    /// 尝试获取可变管理器，未注册或已被借用时返回错误
+    pub fn try_get_manager_mut<'a, T: IComponent + 'static>(
+        &'a self,
+    ) -> Result<RefMut<'a, ComponentManager<T>>, QueryError> {
+        let type_id = TypeId::of::<T>();
+
+        let cell_ref = self
+            .components
+            .get(&type_id)
+            .ok_or(QueryError::NotRegistered(type_name::<T>()))?;
+
+        let manager = cell_ref
+            .try_borrow_mut()
+            .map_err(|_| QueryError::AlreadyBorrowed(type_name::<T>()))?;
+
+        let tick = self.change_tick.get();
+        Ok(RefMut::map(manager, |manager_box| {
+            manager_box.set_change_tick(tick);
+            manager_box
+                .as_any_mut()
+                .downcast_mut::<ComponentManager<T>>()
+                .expect("类型转换失败")
+        }))
+    }
+
+    /// 按 TypeId 借出只读管理器，供并行调度使用
+    pub(crate) fn try_borrow_manager_erased(
+        &self,
+        type_id: TypeId,
+        name: &'static str,
+    ) -> Result<Ref<'_, Box<dyn IComponentManager>>, QueryError> {
+        self.components
+            .get(&type_id)
+            .ok_or(QueryError::NotRegistered(name))?
+            .try_borrow()
+            .map_err(|_| QueryError::AlreadyBorrowed(name))
+    }
+
+    /// 按 TypeId 借出可变管理器，供并行调度使用
+    pub(crate) fn try_borrow_manager_erased_mut(
+        &self,
+        type_id: TypeId,
+        name: &'static str,
+    ) -> Result<RefMut<'_, Box<dyn IComponentManager>>, QueryError> {
+        let mut manager = self
+            .components
+            .get(&type_id)
+            .ok_or(QueryError::NotRegistered(name))?
+            .try_borrow_mut()
+            .map_err(|_| QueryError::AlreadyBorrowed(name))?;
+        manager.set_change_tick(self.change_tick.get());
+        Ok(manager)
+    }
+
+    /// 多组件联合查询，例如 `world.query::<(&Transform, &mut Camera, Option<&Light>)>()`
+    pub fn query<Q: QueryData>(&self) -> Result<Query<'_, Q>, QueryError> {
+        Query::new(self)
+    }
+
+    /// 带过滤器的联合查询，例如 `world.query_filtered::<&Transform, (With<Light>, Without<Camera>)>()`
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(
+        &self,
+    ) -> Result<Query<'_, Q, F>, QueryError> {
+        Query::new(self)
+    }
+
+    /// 所有存活的实体
+    pub fn entities(&self) -> Vec<EntityHandle> {
+        self.entities.borrow().keys().collect()
+    }
+
+    /// 存活的实体数量
+    pub fn entity_count(&self) -> usize {
+        self.entities.borrow().len()
+    }
+
+    /// 实体是否存活
+    pub fn contains(&self, entity: EntityHandle) -> bool {
+        self.entities.borrow().contains_key(entity)
+    }
+
     pub fn spawn_entity(&mut self) -> EntityHandle {
-        self.entities.insert(())
+        self.entities.get_mut().insert(EntityMeta::default())
+    }
+
+    /// 预留一个实体句柄，组件由命令队列稍后添加
+    pub(crate) fn reserve_entity(&self) -> EntityHandle {
+        self.entities.borrow_mut().insert(EntityMeta::default())
+    }
+
+    /// 激活或停用实体，停用的实体及其后代会被内置系统跳过，组件保持不变
+    pub fn set_active(&self, entity: EntityHandle, active: bool) {
+        if let Some(meta) = self.entities.borrow_mut().get_mut(entity) {
+            meta.active = active;
+        }
+    }
+
+    /// 实体自身是否处于激活状态，不考虑父实体
+    pub fn is_active(&self, entity: EntityHandle) -> bool {
+        self.entities
+            .borrow()
+            .get(entity)
+            .is_some_and(|meta| meta.active)
+    }
+
+    /// 实体和它的所有祖先是否都处于激活状态
+    pub fn is_active_in_hierarchy(&self, entity: EntityHandle) -> bool {
+        let mut current = Some(entity);
+        while let Some(e) = current {
+            if !self.is_active(e) {
+                return false;
+            }
+            current = self.get_parent(e);
+        }
+        true
     }
 
+    /// 删除实体，子实体会被递归删除
     pub fn despawn_entity(&mut self, entity: EntityHandle) {
-        if self.entities.remove(entity).is_some() {
-            // 同步删除所有组件
-            for manager in self.components.values() {
-                manager.borrow_mut().remove_abstract(entity);
+        if !self.contains(entity) {
+            return;
+        }
+
+        self.detach(entity);
+
+        let mut stack = vec![entity];
+        while let Some(current) = stack.pop() {
+            if let Some(children) = self.get_manager::<Children>().get(current) {
+                stack.extend(children.iter());
+            }
+
+            if self.entities.get_mut().remove(current).is_some() {
+                // 同步删除所有组件
+                let tick = self.change_tick();
+                let mut removed = Vec::new();
+                for manager in self.components.values() {
+                    let mut manager = manager.borrow_mut();
+                    manager.set_change_tick(tick);
+                    removed.extend(manager.remove_abstract(current));
+                }
+
+                // 管理器借用释放后再调用钩子
+                for mut component in removed {
+                    component.on_remove(current, self);
+                }
             }
         }
     }
 
+    /// 删除所有实体，组件的 on_remove 照常调用
+    pub fn despawn_all(&mut self) {
+        for entity in self.entities() {
+            self.despawn_entity(entity);
+        }
+    }
+
+    /// 添加组件，已存在时覆盖并以旧组件调用 `on_replace`，否则调用 `on_add`
     pub fn add_component<T: IComponent>(&self, entity: EntityHandle, component: T) {
-        self.get_manager_mut::<T>().add(entity, component);
+        let replaced = self.get_manager_mut::<T>().add(entity, component);
+        match replaced {
+            Some(mut old) => old.on_replace(entity, self),
+            None => {
+                let manager = self.get_manager::<T>();
+                if let Some(component) = manager.get(entity) {
+                    component.on_add(entity, self);
+                }
+            }
+        }
     }
 
     pub fn spawn_entity_with<B: ComponentBundle>(&mut self, bundle: B) -> EntityHandle {
@@ -97,43 +307,478 @@ impl World {
         entity
     }
 
+    /// 移除组件并调用 `on_remove`
     pub fn remove_component<T: IComponent>(&self, entity: EntityHandle) -> Option<T> {
-        self.get_manager_mut::<T>().remove(entity)
+        let mut removed = self.get_manager_mut::<T>().remove(entity);
+        if let Some(component) = &mut removed {
+            component.on_remove(entity, self);
+        }
+        removed
     }
 }
 
-pub trait ComponentBundle {
-    fn add_to_entity(self, world: &World, entity: EntityHandle);
+// 全局资源，每种类型只有一个
+impl World {
+    /// 插入资源，已存在的同类型资源会被替换
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.resources
+            .insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)));
+    }
+
+    /// 移除资源
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        let resource = self.resources.remove(&TypeId::of::<R>())?;
+        resource.into_inner().downcast::<R>().ok().map(|r| *r)
+    }
+
+    /// 是否存在资源
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    /// 获取只读资源，不存在时 panic
+    pub fn resource<R: 'static>(&self) -> Ref<'_, R> {
+        self.get_resource::<R>()
+            .unwrap_or_else(|| panic!("资源未插入: {}", type_name::<R>()))
+    }
+
+    /// 获取可变资源，不存在时 panic
+    pub fn resource_mut<R: 'static>(&self) -> RefMut<'_, R> {
+        self.get_resource_mut::<R>()
+            .unwrap_or_else(|| panic!("资源未插入: {}", type_name::<R>()))
+    }
+
+    /// 按 TypeId 借出只读资源，供并行调度使用
+    pub(crate) fn try_borrow_resource_erased(
+        &self,
+        type_id: TypeId,
+        name: &'static str,
+    ) -> Result<Ref<'_, Box<dyn Any>>, QueryError> {
+        self.resources
+            .get(&type_id)
+            .ok_or(QueryError::ResourceNotFound(name))?
+            .try_borrow()
+            .map_err(|_| QueryError::AlreadyBorrowed(name))
+    }
+
+    /// 按 TypeId 借出可变资源，供并行调度使用
+    pub(crate) fn try_borrow_resource_erased_mut(
+        &self,
+        type_id: TypeId,
+        name: &'static str,
+    ) -> Result<RefMut<'_, Box<dyn Any>>, QueryError> {
+        self.resources
+            .get(&type_id)
+            .ok_or(QueryError::ResourceNotFound(name))?
+            .try_borrow_mut()
+            .map_err(|_| QueryError::AlreadyBorrowed(name))
+    }
+
+    /// 获取只读资源
+    pub fn get_resource<R: 'static>(&self) -> Option<Ref<'_, R>> {
+        let cell_ref = self.resources.get(&TypeId::of::<R>())?;
+        Some(Ref::map(cell_ref.borrow(), |resource| {
+            resource.downcast_ref::<R>().expect("类型转换失败")
+        }))
+    }
+
+    /// 获取可变资源
+    pub fn get_resource_mut<R: 'static>(&self) -> Option<RefMut<'_, R>> {
+        let cell_ref = self.resources.get(&TypeId::of::<R>())?;
+        Some(RefMut::map(cell_ref.borrow_mut(), |resource| {
+            resource.downcast_mut::<R>().expect("类型转换失败")
+        }))
+    }
 }
 
-// 为单个组件实现
-impl<T: IComponent> ComponentBundle for T {
-    fn add_to_entity(self, world: &World, entity: EntityHandle) {
-        world.add_component(entity, self);
+// 事件
+impl World {
+    /// 注册事件类型，重复注册会被忽略
+    pub fn add_event<T: 'static>(&mut self) {
+        if self.has_resource::<Events<T>>() {
+            return;
+        }
+        self.insert_resource(Events::<T>::new());
+        self.event_updaters.push(|world| {
+            if let Some(mut events) = world.get_resource_mut::<Events<T>>() {
+                events.update();
+            }
+        });
+    }
+
+    /// 发送事件，事件类型未注册时 panic
+    pub fn send_event<T: 'static>(&self, event: T) {
+        self.event_writer::<T>().send(event);
+    }
+
+    /// 获取事件发送端，事件类型未注册时 panic
+    pub fn event_writer<T: 'static>(&self) -> EventWriter<'_, T> {
+        EventWriter::new(self.resource_mut::<Events<T>>())
+    }
+
+    /// 帧结束时调用，丢弃两帧前的事件
+    pub(crate) fn update_events(&self) {
+        for updater in &self.event_updaters {
+            updater(self);
+        }
     }
 }
 
-// 为元组实现
-impl<T1, T2> ComponentBundle for (T1, T2)
-where
-    T1: IComponent,
-    T2: IComponent,
-{
-    fn add_to_entity(self, world: &World, entity: EntityHandle) {
-        world.add_component(entity, self.0);
-        world.add_component(entity, self.1);
+// 变化检测
+impl World {
+    /// 当前时刻，此时的修改会记录为这个时刻
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick.get()
+    }
+
+    /// 正在运行的系统上一次运行的时刻，用于判断 Added / Changed
+    pub fn last_run_tick(&self) -> u32 {
+        self.last_run_tick.get()
+    }
+
+    pub(crate) fn set_last_run_tick(&self, tick: u32) {
+        self.last_run_tick.set(tick);
+    }
+
+    /// 推进时刻，返回推进前的时刻
+    pub(crate) fn increment_change_tick(&self) -> u32 {
+        let tick = self.change_tick.get();
+        self.change_tick.set(tick + 1);
+        tick
+    }
+
+    /// 正在运行的系统上一次运行之后被删除组件 T 的实体
+    pub fn removed<T: IComponent>(&self) -> Vec<EntityHandle> {
+        self.get_manager::<T>()
+            .removed_since(self.last_run_tick())
+            .collect()
+    }
+
+    /// 清理删除记录，每条记录至少保留一帧
+    pub(crate) fn clear_trackers(&mut self) {
+        let before = self.last_clear_tick;
+        for manager in self.components.values() {
+            manager.borrow_mut().clear_removed(before);
+        }
+        self.last_clear_tick = self.change_tick();
     }
 }
 
-impl<T1, T2, T3> ComponentBundle for (T1, T2, T3)
-where
-    T1: IComponent,
-    T2: IComponent,
-    T3: IComponent,
-{
-    fn add_to_entity(self, world: &World, entity: EntityHandle) {
-        world.add_component(entity, self.0);
-        world.add_component(entity, self.1);
-        world.add_component(entity, self.2);
+// 命令队列
+impl World {
+    /// 获取命令记录器，可在只持有 `&World` 时记录 spawn/despawn 等操作
+    pub fn commands(&self) -> Commands<'_> {
+        Commands::new(self, &self.command_queue)
+    }
+
+    /// 执行所有积压的命令，命令中产生的新命令也会在本次执行
+    pub fn apply_commands(&mut self) {
+        loop {
+            let commands = self.command_queue.get_mut().take();
+            if commands.is_empty() {
+                break;
+            }
+            for command in commands {
+                command(self);
+            }
+        }
+    }
+}
+
+// 层级关系
+impl World {
+    /// 设置父实体，会先脱离原来的父实体
+    pub fn set_parent(
+        &self,
+        child: EntityHandle,
+        parent: EntityHandle,
+    ) -> Result<(), HierarchyError> {
+        if !self.contains(child) || !self.contains(parent) {
+            return Err(HierarchyError::EntityNotFound);
+        }
+
+        // 父实体不能是自己或者自己的后代
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == child {
+                return Err(HierarchyError::CycleDetected);
+            }
+            ancestor = self.get_parent(current);
+        }
+
+        self.detach(child);
+
+        self.add_component(child, Parent(parent));
+        let mut children_mgr = self.get_manager_mut::<Children>();
+        match children_mgr.get_mut(parent) {
+            Some(children) => children.0.push(child),
+            None => {
+                children_mgr.add(parent, Children(vec![child]));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 脱离父实体，成为根实体
+    pub fn detach(&self, child: EntityHandle) {
+        // Parent 的 on_remove 会把 child 从父实体的 Children 中移除
+        self.remove_component::<Parent>(child);
+    }
+
+    /// 从父实体的子列表中移除 child，列表为空时删除 Children 组件
+    pub(crate) fn unlink_child(&self, parent: EntityHandle, child: EntityHandle) {
+        let mut children_mgr = self.get_manager_mut::<Children>();
+        let is_empty = match children_mgr.get_mut(parent) {
+            Some(children) => {
+                children.0.retain(|e| *e != child);
+                children.is_empty()
+            }
+            None => false,
+        };
+        if is_empty {
+            children_mgr.remove(parent);
+        }
+    }
+
+    /// 获取父实体
+    pub fn get_parent(&self, child: EntityHandle) -> Option<EntityHandle> {
+        self.get_manager::<Parent>().get(child).map(|p| p.get())
+    }
+
+    /// 获取所有直接子实体
+    pub fn get_children(&self, parent: EntityHandle) -> Vec<EntityHandle> {
+        self.get_manager::<Children>()
+            .get(parent)
+            .map(|c| c.iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 三层的层级：root -> child -> grandchild
+    fn hierarchy() -> (World, EntityHandle, EntityHandle, EntityHandle) {
+        let mut world = World::new_with_default_registry();
+        let root = world.spawn_entity();
+        let child = world.spawn_entity();
+        let grandchild = world.spawn_entity();
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+        (world, root, child, grandchild)
+    }
+
+    #[test]
+    fn set_parent_links_both_sides() {
+        let (world, root, child, grandchild) = hierarchy();
+        assert_eq!(world.get_parent(child), Some(root));
+        assert_eq!(world.get_children(root), [child]);
+        assert_eq!(world.get_children(child), [grandchild]);
+        assert_eq!(world.get_parent(root), None);
+    }
+
+    #[test]
+    fn set_parent_rejects_cycles() {
+        let (world, root, child, grandchild) = hierarchy();
+        assert!(matches!(
+            world.set_parent(root, grandchild),
+            Err(HierarchyError::CycleDetected)
+        ));
+        assert!(matches!(
+            world.set_parent(child, child),
+            Err(HierarchyError::CycleDetected)
+        ));
+        // 失败时层级保持不变
+        assert_eq!(world.get_parent(root), None);
+        assert_eq!(world.get_parent(child), Some(root));
+    }
+
+    #[test]
+    fn set_parent_rejects_missing_entities() {
+        let (mut world, root, _, grandchild) = hierarchy();
+        world.despawn_entity(grandchild);
+        assert!(matches!(
+            world.set_parent(grandchild, root),
+            Err(HierarchyError::EntityNotFound)
+        ));
+    }
+
+    #[test]
+    fn reparenting_moves_child() {
+        let (world, root, child, grandchild) = hierarchy();
+        world.set_parent(grandchild, root).unwrap();
+        assert_eq!(world.get_parent(grandchild), Some(root));
+        assert_eq!(world.get_children(root), [child, grandchild]);
+        // 原父实体的子列表为空时删除 Children
+        assert!(!world.get_manager::<Children>().has(child));
+    }
+
+    #[test]
+    fn detach_makes_root() {
+        let (world, root, child, _) = hierarchy();
+        world.detach(child);
+        assert_eq!(world.get_parent(child), None);
+        assert!(world.get_children(root).is_empty());
+        assert!(!world.get_manager::<Children>().has(root));
+    }
+
+    #[test]
+    fn removing_parent_component_updates_children() {
+        let (world, root, child, _) = hierarchy();
+        let removed = world.remove_component::<Parent>(child);
+        assert_eq!(removed.map(|parent| parent.get()), Some(root));
+        assert!(world.get_children(root).is_empty());
+    }
+
+    #[test]
+    fn despawn_removes_descendants() {
+        let (mut world, root, child, grandchild) = hierarchy();
+        let other = world.spawn_entity();
+
+        world.despawn_entity(root);
+        assert!(!world.contains(root));
+        assert!(!world.contains(child));
+        assert!(!world.contains(grandchild));
+        assert!(world.contains(other));
+        assert!(world.get_manager::<Parent>().is_empty());
+        assert!(world.get_manager::<Children>().is_empty());
+    }
+
+    #[test]
+    fn despawn_child_keeps_parent() {
+        let (mut world, root, child, grandchild) = hierarchy();
+        world.despawn_entity(child);
+        assert!(world.contains(root));
+        assert!(!world.contains(grandchild));
+        assert!(world.get_children(root).is_empty());
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+
+    #[test]
+    fn resource_insert_get_and_replace() {
+        let mut world = World::new_with_default_registry();
+        assert!(!world.has_resource::<Score>());
+        assert!(world.get_resource::<Score>().is_none());
+
+        world.insert_resource(Score(1));
+        assert!(world.has_resource::<Score>());
+        world.resource_mut::<Score>().0 += 1;
+        assert_eq!(*world.resource::<Score>(), Score(2));
+
+        // 同类型的资源只有一个
+        world.insert_resource(Score(10));
+        assert_eq!(*world.resource::<Score>(), Score(10));
+        assert_eq!(world.remove_resource::<Score>(), Some(Score(10)));
+        assert!(world.get_resource_mut::<Score>().is_none());
+        assert_eq!(world.remove_resource::<Score>(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Score")]
+    fn missing_resource_panics_with_type_name() {
+        let world = World::new_with_default_registry();
+        let _ = world.resource::<Score>();
+    }
+
+    #[test]
+    fn erased_borrows_report_missing_and_conflicting_access() {
+        let mut world = World::new_with_default_registry();
+        let id = TypeId::of::<Score>();
+        assert!(matches!(
+            world.try_borrow_resource_erased(id, "Score"),
+            Err(QueryError::ResourceNotFound("Score"))
+        ));
+
+        world.insert_resource(Score(0));
+        let reader = world.try_borrow_resource_erased(id, "Score").unwrap();
+        assert!(world.try_borrow_resource_erased(id, "Score").is_ok());
+        assert!(matches!(
+            world.try_borrow_resource_erased_mut(id, "Score"),
+            Err(QueryError::AlreadyBorrowed("Score"))
+        ));
+        drop(reader);
+        assert!(world.try_borrow_resource_erased_mut(id, "Score").is_ok());
+    }
+
+    /// 钩子调用记录
+    #[derive(Default)]
+    struct HookLog(Vec<String>);
+
+    struct Hooked(u32);
+
+    impl IComponent for Hooked {
+        fn on_add(&self, _entity: EntityHandle, world: &World) {
+            world
+                .resource_mut::<HookLog>()
+                .0
+                .push(format!("add {}", self.0));
+        }
+
+        fn on_replace(&mut self, _entity: EntityHandle, world: &World) {
+            world
+                .resource_mut::<HookLog>()
+                .0
+                .push(format!("replace {}", self.0));
+        }
+
+        fn on_remove(&mut self, _entity: EntityHandle, world: &World) {
+            world
+                .resource_mut::<HookLog>()
+                .0
+                .push(format!("remove {}", self.0));
+        }
+    }
+
+    fn hooked_world() -> World {
+        let mut world = World::new_with_default_registry();
+        world.register_component::<Hooked>();
+        world.insert_resource(HookLog::default());
+        world
+    }
+
+    fn take_log(world: &World) -> Vec<String> {
+        std::mem::take(&mut world.resource_mut::<HookLog>().0)
+    }
+
+    #[test]
+    fn hooks_fire_once_on_add_replace_and_remove() {
+        let mut world = hooked_world();
+        let entity = world.spawn_entity();
+
+        world.add_component(entity, Hooked(1));
+        assert_eq!(take_log(&world), ["add 1"]);
+        // 覆盖时以旧组件调用 on_replace，不再调用 on_add
+        world.add_component(entity, Hooked(2));
+        assert_eq!(take_log(&world), ["replace 1"]);
+        assert_eq!(
+            world.remove_component::<Hooked>(entity).map(|h| h.0),
+            Some(2)
+        );
+        assert_eq!(take_log(&world), ["remove 2"]);
+        assert!(world.remove_component::<Hooked>(entity).is_none());
+        assert!(take_log(&world).is_empty());
+    }
+
+    #[test]
+    fn despawn_calls_on_remove_for_entity_and_descendants() {
+        let (mut world, root, child, _) = hierarchy();
+        world.register_component::<Hooked>();
+        world.insert_resource(HookLog::default());
+        world.add_component(root, Hooked(1));
+        world.add_component(child, Hooked(2));
+        take_log(&world);
+
+        world.despawn_entity(root);
+        let mut log = take_log(&world);
+        log.sort();
+        assert_eq!(log, ["remove 1", "remove 2"]);
+
+        // 已删除的实体不会再次触发
+        world.despawn_entity(root);
+        assert!(take_log(&world).is_empty());
     }
 }
@@ -15,8 +15,18 @@ pub enum AppEvent {
     MouseButton { button: MouseButton, action: Action },
     /// resize窗口
     Resize { width: i32, height: i32 },
+    /// 窗口获得或失去焦点
+    Focus { focused: bool },
+    /// 窗口最小化或恢复
+    Iconify { iconified: bool },
 }
 
+/// 应用退出事件
+///
+/// 只在 `App::shutdown` 中、关闭系统之前发送一次，普通帧的 update 中读不到，
+/// 只能在 `ISystem::shutdown` 和行为的 on_destroy 中读取。
+pub struct AppExit;
+
 /// 引用事件队列
 pub struct AppEventQueue {
     queue: Vec<AppEvent>,
@@ -56,6 +56,18 @@ impl InputState {
         self.pressed_keys.remove(k);
     }
 
+    /// 释放所有按键和鼠标按钮，窗口失去焦点时调用
+    pub(crate) fn release_all(&mut self) {
+        self.pressed_keys.clear();
+        self.pressed_mouse_buttons.clear();
+    }
+
+    /// 下一次鼠标移动只记录位置，避免光标模式切换时产生跳变
+    pub(crate) fn reset_cursor(&mut self) {
+        self.is_first_cursor_move = true;
+        self.cursor_delta = Vec2::ZERO;
+    }
+
     /// 获取滚动的delta数据
     pub fn get_scroll_delta(&self) -> &Vec2 {
         &self.scroll_delta
@@ -62,6 +62,7 @@ impl DefaultPipeline {
                 ),
                 true,
             )
+            .with_name("main")
             .with_sort(|a, b| {
                 a.get_depth()
                     .partial_cmp(&b.get_depth())
@@ -90,13 +91,14 @@ impl DefaultPipeline {
                 ),
                 false,
             )
+            .with_name("transparent")
             .with_sort(|a, b| {
                 b.get_depth()
                     .partial_cmp(&a.get_depth())
                     .unwrap_or(Ordering::Equal)
             }),
         );
-        pipeline.insert(Pass::new(
+        let outline = Pass::new(
             Self::outline_pass(),
             10,
             RenderState::new(
@@ -116,8 +118,10 @@ impl DefaultPipeline {
                 PolygonMode::default(),
             ),
             false,
-        ));
-        pipeline.insert(Pass::new(
+        )
+        .with_name("outline");
+        pipeline.insert(outline);
+        let debug = Pass::new(
             Self::debug_pass(),
             15,
             RenderState::new(
@@ -138,8 +142,10 @@ impl DefaultPipeline {
                 PolygonMode::default(),
             ),
             false,
-        ));
-        pipeline.insert(Pass::new(
+        )
+        .with_name("debug");
+        pipeline.insert(debug);
+        let skybox = Pass::new(
             Self::skybox_pass(),
             20,
             RenderState::new(
@@ -159,8 +165,10 @@ impl DefaultPipeline {
                 PolygonMode::default(),
             ),
             false,
-        ));
-        pipeline.insert(Pass::new(
+        )
+        .with_name("skybox");
+        pipeline.insert(skybox);
+        let ui = Pass::new(
             Self::ui_pass(),
             30,
             RenderState::new(
@@ -180,7 +188,9 @@ impl DefaultPipeline {
                 PolygonMode::default(),
             ),
             false,
-        ));
+        )
+        .with_name("ui");
+        pipeline.insert(ui);
         pipeline
     }
 }
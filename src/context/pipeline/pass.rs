@@ -123,6 +123,8 @@ impl SingleJob {
 
 pub struct Pass {
     pub id: PassId,
+    /// 用于日志和性能分析，默认为 id 的十六进制
+    pub name: String,
     pub priority: i32,
     pub is_opaque: bool,
     pub sort_func: Option<Box<dyn Fn(&SingleJob, &SingleJob) -> Ordering>>,
@@ -133,6 +135,7 @@ impl Pass {
     pub fn new(id: PassId, priority: i32, state: RenderState, is_opaque: bool) -> Self {
         Self {
             id,
+            name: format!("{:08x}", id.raw()),
             priority,
             default_state: state,
             sort_func: None,
@@ -140,6 +143,11 @@ impl Pass {
         }
     }
 
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
     pub fn with_sort<F>(mut self, sort_func: F) -> Self
     where
         F: Fn(&SingleJob, &SingleJob) -> Ordering + 'static,
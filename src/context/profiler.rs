@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::{IClock, SystemClock};
+
+/// 耗时记录的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileCategory {
+    Update,
+    FixedUpdate,
+    /// GPU 上的 Pass 和后处理
+    Gpu,
+}
+
+impl ProfileCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProfileCategory::Update => "update",
+            ProfileCategory::FixedUpdate => "fixed_update",
+            ProfileCategory::Gpu => "gpu",
+        }
+    }
+}
+
+/// 一段耗时，时间单位为秒，时间基准为 App 的时钟
+#[derive(Debug, Clone)]
+pub struct ProfileSpan {
+    pub name: String,
+    pub category: ProfileCategory,
+    /// 0 为主线程，并行系统为线程池中的线程编号加 1
+    pub thread: usize,
+    pub start: f64,
+    pub duration: f64,
+}
+
+/// 一帧的性能数据
+///
+/// GPU 耗时通过异步查询获得，会在几帧之后补充到对应的帧上。
+/// GPU 查询只给出耗时，`gpu_spans` 的 `start` 是从帧起点开始依次排列得到的，不是真实的开始时间。
+#[derive(Debug, Clone, Default)]
+pub struct FrameProfile {
+    pub index: u64,
+    pub start: f64,
+    pub duration: f64,
+    pub cpu_spans: Vec<ProfileSpan>,
+    pub gpu_spans: Vec<ProfileSpan>,
+    pub counters: BTreeMap<String, f64>,
+}
+
+impl FrameProfile {
+    /// 同名 CPU 记录的总耗时，fixed_update 一帧可能运行多次
+    pub fn cpu_time(&self, name: &str) -> f64 {
+        Self::total(&self.cpu_spans, name)
+    }
+
+    pub fn gpu_time(&self, name: &str) -> f64 {
+        Self::total(&self.gpu_spans, name)
+    }
+
+    fn total(spans: &[ProfileSpan], name: &str) -> f64 {
+        spans
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| span.duration)
+            .sum()
+    }
+}
+
+/// 性能分析器，作为资源存放在 World 中
+///
+/// 记录每个系统的 CPU 耗时、每个 Pass 和后处理的 GPU 耗时以及每帧的计数，
+/// 保留最近若干帧，可以导出为 Chrome trace 格式（chrome://tracing 或 Perfetto）。
+/// 时间戳取自 App 的时钟，使用 `ManualClock` 时与手动推进的时间一致。
+pub struct Profiler {
+    enabled: bool,
+    history_len: usize,
+    clock: Rc<dyn IClock>,
+    frames: VecDeque<FrameProfile>,
+    current: FrameProfile,
+    frame_count: u64,
+}
+
+impl Profiler {
+    pub fn new(enabled: bool, history_len: usize) -> Self {
+        Self {
+            enabled,
+            history_len: history_len.max(1),
+            clock: Rc::new(SystemClock::new()),
+            frames: VecDeque::new(),
+            current: FrameProfile::default(),
+            frame_count: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history_len
+    }
+
+    /// 设置保留的帧数，多出的旧帧立即丢弃
+    pub fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len.max(1);
+        self.trim();
+    }
+
+    /// 已完成的帧，从旧到新
+    pub fn frames(&self) -> impl Iterator<Item = &FrameProfile> {
+        self.frames.iter()
+    }
+
+    pub fn last_frame(&self) -> Option<&FrameProfile> {
+        self.frames.back()
+    }
+
+    pub fn frame(&self, index: u64) -> Option<&FrameProfile> {
+        self.frames.iter().find(|frame| frame.index == index)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// 历史中每帧同名 CPU 记录的平均耗时
+    pub fn average_cpu_time(&self, name: &str) -> Option<f64> {
+        self.average(|frame| frame.cpu_time(name))
+    }
+
+    /// 历史中每帧同名 GPU 记录的平均耗时，还没有结果的帧不计入
+    pub fn average_gpu_time(&self, name: &str) -> Option<f64> {
+        let frames: Vec<_> = self
+            .frames
+            .iter()
+            .filter(|frame| frame.gpu_spans.iter().any(|span| span.name == name))
+            .collect();
+        if frames.is_empty() {
+            return None;
+        }
+        let total: f64 = frames.iter().map(|frame| frame.gpu_time(name)).sum();
+        Some(total / frames.len() as f64)
+    }
+
+    /// 历史中的平均帧时间
+    pub fn average_frame_time(&self) -> Option<f64> {
+        self.average(|frame| frame.duration)
+    }
+
+    fn average(&self, f: impl Fn(&FrameProfile) -> f64) -> Option<f64> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let total: f64 = self.frames.iter().map(f).sum();
+        Some(total / self.frames.len() as f64)
+    }
+
+    /// 设置本帧的计数
+    pub fn set_counter(&mut self, name: &str, value: f64) {
+        if self.enabled {
+            self.current.counters.insert(name.to_string(), value);
+        }
+    }
+
+    /// 累加本帧的计数
+    pub fn add_counter(&mut self, name: &str, value: f64) {
+        if self.enabled {
+            *self.current.counters.entry(name.to_string()).or_default() += value;
+        }
+    }
+
+    /// 当前帧的编号
+    pub fn frame_index(&self) -> u64 {
+        self.current.index
+    }
+
+    /// 当前时刻，单位为秒
+    pub(crate) fn now(&self) -> f64 {
+        self.clock.now()
+    }
+
+    /// 使用 App 的时钟，由 App 在创建和替换时钟时设置
+    pub(crate) fn set_clock(&mut self, clock: Rc<dyn IClock>) {
+        self.clock = clock;
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        self.current = FrameProfile {
+            index: self.frame_count,
+            start: self.now(),
+            ..Default::default()
+        };
+        self.frame_count += 1;
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let mut frame = std::mem::take(&mut self.current);
+        frame.duration = self.now() - frame.start;
+        self.frames.push_back(frame);
+        self.trim();
+    }
+
+    pub(crate) fn record_cpu(
+        &mut self,
+        name: &str,
+        category: ProfileCategory,
+        thread: usize,
+        start: f64,
+        duration: f64,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        self.current.cpu_spans.push(ProfileSpan {
+            name: name.to_string(),
+            category,
+            thread,
+            start,
+            duration,
+        });
+    }
+
+    /// 补充某一帧的 GPU 耗时
+    ///
+    /// GL_TIME_ELAPSED 只有耗时，同一帧的 GPU 记录首尾相接地排列在帧的起点之后，
+    /// 起点只用于显示顺序，与 CPU 记录的时间没有对应关系。
+    pub(crate) fn record_gpu(&mut self, frame_index: u64, name: &str, duration: f64) {
+        if !self.enabled {
+            return;
+        }
+        let frame = if self.current.index == frame_index {
+            &mut self.current
+        } else {
+            // 帧已经被丢弃
+            let Some(frame) = self
+                .frames
+                .iter_mut()
+                .rev()
+                .find(|frame| frame.index == frame_index)
+            else {
+                return;
+            };
+            frame
+        };
+        let start = frame
+            .gpu_spans
+            .last()
+            .map_or(frame.start, |span| span.start + span.duration);
+        frame.gpu_spans.push(ProfileSpan {
+            name: name.to_string(),
+            category: ProfileCategory::Gpu,
+            thread: 0,
+            start,
+            duration,
+        });
+    }
+
+    fn trim(&mut self) {
+        while self.frames.len() > self.history_len {
+            self.frames.pop_front();
+        }
+    }
+
+    /// 导出历史为 Chrome trace-event JSON
+    ///
+    /// CPU 记录按线程分开显示，计数显示为计数器。
+    /// GPU 记录只有耗时是测量值，显示在单独的进程中，不能和 CPU 记录对齐比较。
+    pub fn to_chrome_trace(&self) -> String {
+        const CPU_PID: u32 = 1;
+        const GPU_PID: u32 = 2;
+
+        let mut events = vec![
+            metadata_event(CPU_PID, "CPU"),
+            metadata_event(GPU_PID, "GPU (durations only, start times are synthetic)"),
+        ];
+        for frame in &self.frames {
+            events.push(complete_event(
+                &format!("frame {}", frame.index),
+                "frame",
+                CPU_PID,
+                0,
+                frame.start,
+                frame.duration,
+            ));
+            for span in &frame.cpu_spans {
+                events.push(complete_event(
+                    &span.name,
+                    span.category.as_str(),
+                    CPU_PID,
+                    span.thread,
+                    span.start,
+                    span.duration,
+                ));
+            }
+            for span in &frame.gpu_spans {
+                events.push(complete_event(
+                    &span.name,
+                    span.category.as_str(),
+                    GPU_PID,
+                    span.thread,
+                    span.start,
+                    span.duration,
+                ));
+            }
+            for (name, value) in &frame.counters {
+                events.push(format!(
+                    r#"{{"name":"{}","ph":"C","pid":{},"ts":{:.3},"args":{{"value":{}}}}}"#,
+                    escape_json(name),
+                    CPU_PID,
+                    frame.start * 1e6,
+                    value
+                ));
+            }
+        }
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_chrome_trace())
+    }
+}
+
+fn metadata_event(pid: u32, name: &str) -> String {
+    format!(
+        r#"{{"name":"process_name","ph":"M","pid":{},"args":{{"name":"{}"}}}}"#,
+        pid, name
+    )
+}
+
+fn complete_event(
+    name: &str,
+    category: &str,
+    pid: u32,
+    tid: usize,
+    start: f64,
+    duration: f64,
+) -> String {
+    format!(
+        r#"{{"name":"{}","cat":"{}","ph":"X","pid":{},"tid":{},"ts":{:.3},"dur":{:.3}}}"#,
+        escape_json(name),
+        category,
+        pid,
+        tid,
+        start * 1e6,
+        duration * 1e6
+    )
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+
+    fn profiler(history_len: usize) -> (Profiler, ManualClock) {
+        let clock = ManualClock::new();
+        let mut profiler = Profiler::new(true, history_len);
+        profiler.set_clock(Rc::new(clock.clone()));
+        (profiler, clock)
+    }
+
+    fn run_frame(profiler: &mut Profiler, clock: &ManualClock) {
+        profiler.begin_frame();
+        clock.advance(1.0);
+        profiler.end_frame();
+    }
+
+    #[test]
+    fn escapes_quotes_backslashes_and_control_characters() {
+        assert_eq!(
+            escape_json("a\"b\\c\nd\re\tf\u{1}g\u{1f}é"),
+            "a\\\"b\\\\c\\nd\\re\\tf\\u0001g\\u001fé"
+        );
+    }
+
+    #[test]
+    fn chrome_trace_escapes_span_and_counter_names() {
+        let (mut profiler, clock) = profiler(4);
+        profiler.begin_frame();
+        profiler.record_cpu("say \"hi\"", ProfileCategory::Update, 0, 0.0, 0.5);
+        profiler.set_counter("path\\to\n", 1.0);
+        clock.advance(1.0);
+        profiler.end_frame();
+
+        let trace = profiler.to_chrome_trace();
+        assert!(trace.contains(r#""name":"say \"hi\"","cat":"update""#));
+        assert!(trace.contains(r#""name":"path\\to\n","ph":"C""#));
+        // 除了事件之间的换行，输出中没有未转义的控制字符
+        assert!(trace.chars().all(|c| c == '\n' || c >= ' '));
+        assert_eq!(trace.lines().count(), 7);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_frames() {
+        let (mut profiler, clock) = profiler(2);
+        for _ in 0..3 {
+            run_frame(&mut profiler, &clock);
+        }
+        let indices: Vec<u64> = profiler.frames().map(|frame| frame.index).collect();
+        assert_eq!(indices, [1, 2]);
+
+        profiler.set_history_len(1);
+        let indices: Vec<u64> = profiler.frames().map(|frame| frame.index).collect();
+        assert_eq!(indices, [2]);
+    }
+
+    #[test]
+    fn gpu_spans_are_laid_out_from_frame_start() {
+        let (mut profiler, clock) = profiler(2);
+        clock.set(5.0);
+        for _ in 0..3 {
+            run_frame(&mut profiler, &clock);
+        }
+        // 已经丢弃的帧的结果被忽略
+        profiler.record_gpu(0, "Opaque", 0.25);
+        profiler.record_gpu(2, "Opaque", 0.25);
+        profiler.record_gpu(2, "Postprocess", 0.5);
+
+        assert!(
+            profiler
+                .frames()
+                .all(|frame| frame.index == 2 || frame.gpu_spans.is_empty())
+        );
+        let frame = profiler.frame(2).unwrap();
+        let spans: Vec<(&str, f64, f64)> = frame
+            .gpu_spans
+            .iter()
+            .map(|span| (span.name.as_str(), span.start, span.duration))
+            .collect();
+        assert_eq!(spans, [("Opaque", 7.0, 0.25), ("Postprocess", 7.25, 0.5)]);
+    }
+}
@@ -0,0 +1,220 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// 固定更新的时间信息
+pub struct FixedTime {
+    delta: f64,
+    alpha: f32,
+    steps: u32,
+    dropped_time: f64,
+    total_dropped_time: f64,
+}
+
+impl FixedTime {
+    pub fn new(delta: f64) -> Self {
+        Self {
+            delta,
+            alpha: 0.0,
+            steps: 0,
+            dropped_time: 0.0,
+            total_dropped_time: 0.0,
+        }
+    }
+
+    /// 固定更新的时间步长
+    pub fn delta(&self) -> f32 {
+        self.delta as f32
+    }
+
+    pub fn delta_f64(&self) -> f64 {
+        self.delta
+    }
+
+    /// 距上一次 fixed_update 经过的时间占步长的比例，范围 0 到 1，用于渲染插值
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub(crate) fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    /// 本帧运行的 fixed_update 次数
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// 本帧因超过上限而丢弃的时间
+    pub fn dropped_time(&self) -> f64 {
+        self.dropped_time
+    }
+
+    /// 累计丢弃的时间
+    pub fn total_dropped_time(&self) -> f64 {
+        self.total_dropped_time
+    }
+
+    pub(crate) fn record_frame(&mut self, steps: u32, dropped_time: f64) {
+        self.steps = steps;
+        self.dropped_time = dropped_time;
+        self.total_dropped_time += dropped_time;
+    }
+}
+
+/// 帧时间，每帧开始时由 App 更新
+///
+/// 暂停时缩放后的时间和 fixed_update 停止推进，未缩放的时间照常推进，可用于界面等不受暂停影响的逻辑。
+pub struct Time {
+    delta: f64,
+    unscaled_delta: f64,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    frame_count: u64,
+    time_scale: f64,
+    is_paused: bool,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            frame_count: 0,
+            time_scale: 1.0,
+            is_paused: false,
+        }
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 本帧经过的时间，受缩放和暂停影响
+    pub fn delta(&self) -> f32 {
+        self.delta as f32
+    }
+
+    pub fn delta_f64(&self) -> f64 {
+        self.delta
+    }
+
+    /// 本帧经过的真实时间
+    pub fn unscaled_delta(&self) -> f32 {
+        self.unscaled_delta as f32
+    }
+
+    pub fn unscaled_delta_f64(&self) -> f64 {
+        self.unscaled_delta
+    }
+
+    /// 累计的缩放后时间
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// 累计的真实时间
+    pub fn unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    /// 已经开始的帧数，第一帧为 1
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// 设置时间缩放，小于 1 为慢动作，负数按 0 处理
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn pause(&mut self) {
+        self.is_paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.is_paused = false;
+    }
+
+    /// 开始新的一帧，返回缩放后的时间
+    pub(crate) fn advance(&mut self, unscaled_delta: f64) -> f64 {
+        self.unscaled_delta = unscaled_delta;
+        self.unscaled_elapsed += unscaled_delta;
+        self.delta = if self.is_paused {
+            0.0
+        } else {
+            unscaled_delta * self.time_scale
+        };
+        self.elapsed += self.delta;
+        self.frame_count += 1;
+        self.delta
+    }
+}
+
+/// App 使用的时钟，返回以秒为单位、单调递增的时间
+pub trait IClock {
+    fn now(&self) -> f64;
+}
+
+/// 系统时钟，从创建时开始计时
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IClock for SystemClock {
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+}
+
+/// 手动推进的时钟，用于测试和回放，克隆的时钟共享同一个时间
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<f64>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, now: f64) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, delta: f64) {
+        self.now.set(self.now.get() + delta);
+    }
+}
+
+impl IClock for ManualClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+}
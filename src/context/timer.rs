@@ -0,0 +1,573 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use glam::Vec3;
+use slotmap::{SlotMap, new_key_type};
+
+use crate::{AppContext, EntityHandle, Transform, Translation};
+
+new_key_type! {
+    pub struct TimerHandle;
+}
+
+type Callback = Box<dyn FnMut(Rc<RefCell<AppContext>>)>;
+
+// 把只调用一次的回调包装为 FnMut
+fn once<F>(f: F) -> Callback
+where
+    F: FnOnce(Rc<RefCell<AppContext>>) + 'static,
+{
+    let mut f = Some(f);
+    Box::new(move |context| {
+        if let Some(f) = f.take() {
+            f(context);
+        }
+    })
+}
+
+struct Timer {
+    remaining: f32,
+    // 重复间隔，None 为单次定时器
+    interval: Option<f32>,
+    callback: Callback,
+}
+
+impl Timer {
+    /// 推进时间并触发回调，返回定时器是否结束
+    fn tick(&mut self, dt: f32, context: &Rc<RefCell<AppContext>>) -> bool {
+        self.remaining -= dt;
+        while self.remaining <= 0.0 {
+            (self.callback)(context.clone());
+            match self.interval {
+                Some(interval) if interval > 0.0 => self.remaining += interval,
+                // 间隔不大于 0 时每帧触发一次
+                Some(_) => {
+                    self.remaining = 0.0;
+                    return false;
+                }
+                None => return true,
+            }
+        }
+        false
+    }
+}
+
+enum Step {
+    Wait(f32),
+    Call(Callback),
+    Over {
+        duration: f32,
+        update: Box<dyn FnMut(Rc<RefCell<AppContext>>, f32)>,
+    },
+    Until(Box<dyn FnMut(Rc<RefCell<AppContext>>) -> bool>),
+}
+
+/// 按顺序执行的步骤序列，用来代替行为中手写的状态机
+///
+/// ```ignore
+/// let sequence = Sequence::new()
+///     .wait(1.0)
+///     .move_to(entity, Vec3::new(0.0, 5.0, 0.0), 2.0)
+///     .despawn(entity);
+/// world.resource_mut::<Timers>().sequence_for(entity, sequence);
+/// ```
+#[derive(Default)]
+pub struct Sequence {
+    steps: VecDeque<Step>,
+    // 当前步骤已经经过的时间
+    elapsed: f32,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 等待一段时间
+    pub fn wait(mut self, seconds: f32) -> Self {
+        self.steps.push_back(Step::Wait(seconds));
+        self
+    }
+
+    /// 调用一次回调
+    pub fn then<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(Rc<RefCell<AppContext>>) + 'static,
+    {
+        self.steps.push_back(Step::Call(once(f)));
+        self
+    }
+
+    /// 在一段时间内每帧调用回调，参数为 0 到 1 的进度，最后一次调用的进度为 1
+    pub fn over<F>(mut self, duration: f32, update: F) -> Self
+    where
+        F: FnMut(Rc<RefCell<AppContext>>, f32) + 'static,
+    {
+        self.steps.push_back(Step::Over {
+            duration,
+            update: Box::new(update),
+        });
+        self
+    }
+
+    /// 每帧检查条件，直到条件成立
+    pub fn wait_until<F>(mut self, condition: F) -> Self
+    where
+        F: FnMut(Rc<RefCell<AppContext>>) -> bool + 'static,
+    {
+        self.steps.push_back(Step::Until(Box::new(condition)));
+        self
+    }
+
+    /// 在一段时间内把实体从当前位置线性移动到目标位置
+    pub fn move_to(self, entity: EntityHandle, target: Vec3, duration: f32) -> Self {
+        let mut start = None;
+        self.over(duration, move |context, t| {
+            let context = context.borrow();
+            let world = context.world.borrow();
+            let mut transform_mgr = world.get_manager_mut::<Transform>();
+            let Some(transform) = transform_mgr.get_mut(entity) else {
+                return;
+            };
+            // 起点在这一步第一次运行时确定
+            let from = *start.get_or_insert(Vec3::from(transform.get_translation().data));
+            transform.set_translation(Translation::from_vec(from.lerp(target, t)));
+        })
+    }
+
+    /// 删除实体，实体上剩余的定时器和序列随之取消
+    pub fn despawn(self, entity: EntityHandle) -> Self {
+        self.then(move |context| {
+            context.borrow().world.borrow().commands().despawn(entity);
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// 推进时间并执行步骤，返回序列是否结束
+    fn tick(&mut self, dt: f32, context: &Rc<RefCell<AppContext>>) -> bool {
+        // 本帧还可以分配给后续步骤的时间
+        let mut budget = dt;
+        while let Some(step) = self.steps.front_mut() {
+            match step {
+                Step::Wait(seconds) => {
+                    self.elapsed += budget;
+                    if self.elapsed < *seconds {
+                        return false;
+                    }
+                    budget = self.elapsed - *seconds;
+                }
+                Step::Call(callback) => callback(context.clone()),
+                Step::Over { duration, update } => {
+                    self.elapsed += budget;
+                    if self.elapsed < *duration {
+                        update(context.clone(), self.elapsed / *duration);
+                        return false;
+                    }
+                    budget = self.elapsed - *duration;
+                    update(context.clone(), 1.0);
+                }
+                Step::Until(condition) => {
+                    if !condition(context.clone()) {
+                        return false;
+                    }
+                }
+            }
+            self.steps.pop_front();
+            self.elapsed = 0.0;
+        }
+        true
+    }
+}
+
+enum Task {
+    Timer(Timer),
+    Sequence(Sequence),
+}
+
+impl Task {
+    fn tick(&mut self, dt: f32, context: &Rc<RefCell<AppContext>>) -> bool {
+        match self {
+            Task::Timer(timer) => timer.tick(dt, context),
+            Task::Sequence(sequence) => sequence.tick(dt, context),
+        }
+    }
+}
+
+struct TaskSlot {
+    owner: Option<EntityHandle>,
+    // 运行期间被 TimerSystem 取出
+    task: Option<Task>,
+}
+
+/// 定时器服务，作为资源存放在 World 中，由 TimerSystem 每帧推进
+///
+/// 绑定到实体的定时器和序列在实体删除后自动取消，实体停用期间暂停计时。
+/// 回调运行时不持有 World 的借用，可以在回调中再次访问 `Timers`。
+#[derive(Default)]
+pub struct Timers {
+    tasks: SlotMap<TimerHandle, TaskSlot>,
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 若干秒后调用一次
+    pub fn after<F>(&mut self, seconds: f32, f: F) -> TimerHandle
+    where
+        F: FnOnce(Rc<RefCell<AppContext>>) + 'static,
+    {
+        self.add_timer(None, seconds, None, once(f))
+    }
+
+    /// 每隔若干秒调用一次，直到被取消
+    pub fn every<F>(&mut self, interval: f32, f: F) -> TimerHandle
+    where
+        F: FnMut(Rc<RefCell<AppContext>>) + 'static,
+    {
+        self.add_timer(None, interval, Some(interval), Box::new(f))
+    }
+
+    /// 运行序列
+    pub fn sequence(&mut self, sequence: Sequence) -> TimerHandle {
+        self.add_task(None, Task::Sequence(sequence))
+    }
+
+    /// 绑定到实体的 `after`
+    pub fn after_for<F>(&mut self, entity: EntityHandle, seconds: f32, f: F) -> TimerHandle
+    where
+        F: FnOnce(Rc<RefCell<AppContext>>) + 'static,
+    {
+        self.add_timer(Some(entity), seconds, None, once(f))
+    }
+
+    /// 绑定到实体的 `every`
+    pub fn every_for<F>(&mut self, entity: EntityHandle, interval: f32, f: F) -> TimerHandle
+    where
+        F: FnMut(Rc<RefCell<AppContext>>) + 'static,
+    {
+        self.add_timer(Some(entity), interval, Some(interval), Box::new(f))
+    }
+
+    /// 绑定到实体的 `sequence`
+    pub fn sequence_for(&mut self, entity: EntityHandle, sequence: Sequence) -> TimerHandle {
+        self.add_task(Some(entity), Task::Sequence(sequence))
+    }
+
+    /// 取消定时器或序列，返回它是否还未结束
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        self.tasks.remove(handle).is_some()
+    }
+
+    /// 取消绑定到实体的所有定时器和序列
+    pub fn cancel_for(&mut self, entity: EntityHandle) {
+        self.tasks.retain(|_, slot| slot.owner != Some(entity));
+    }
+
+    /// 定时器或序列是否还未结束
+    pub fn contains(&self, handle: TimerHandle) -> bool {
+        self.tasks.contains_key(handle)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    fn add_timer(
+        &mut self,
+        owner: Option<EntityHandle>,
+        delay: f32,
+        interval: Option<f32>,
+        callback: Callback,
+    ) -> TimerHandle {
+        let timer = Timer {
+            remaining: delay,
+            interval,
+            callback,
+        };
+        self.add_task(owner, Task::Timer(timer))
+    }
+
+    fn add_task(&mut self, owner: Option<EntityHandle>, task: Task) -> TimerHandle {
+        self.tasks.insert(TaskSlot {
+            owner,
+            task: Some(task),
+        })
+    }
+
+    /// 所有任务的句柄和绑定的实体
+    pub(crate) fn handles(&self) -> Vec<(TimerHandle, Option<EntityHandle>)> {
+        self.tasks
+            .iter()
+            .map(|(handle, slot)| (handle, slot.owner))
+            .collect()
+    }
+
+    fn take(&mut self, handle: TimerHandle) -> Option<Task> {
+        self.tasks.get_mut(handle)?.task.take()
+    }
+
+    // 运行期间被取消的任务直接丢弃
+    fn put_back(&mut self, handle: TimerHandle, task: Task) {
+        if let Some(slot) = self.tasks.get_mut(handle) {
+            slot.task = Some(task);
+        }
+    }
+
+    /// 推进一个任务，运行回调时不持有 World 的借用
+    pub(crate) fn tick_task(context: &Rc<RefCell<AppContext>>, handle: TimerHandle, dt: f32) {
+        let task = {
+            let app = context.borrow();
+            let world = app.world.borrow();
+            world.resource_mut::<Timers>().take(handle)
+        };
+        let Some(mut task) = task else {
+            return;
+        };
+
+        let finished = task.tick(dt, context);
+
+        let app = context.borrow();
+        let world = app.world.borrow();
+        let mut timers = world.resource_mut::<Timers>();
+        if finished {
+            timers.cancel(handle);
+        } else {
+            timers.put_back(handle, task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppConfig, ISystem, TimerSystem};
+    use std::cell::Cell;
+
+    struct Harness {
+        context: Rc<RefCell<AppContext>>,
+        system: TimerSystem,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let context = Rc::new(RefCell::new(AppContext::new(AppConfig::default())));
+            context
+                .borrow()
+                .world
+                .borrow_mut()
+                .insert_resource(Timers::new());
+            Self {
+                context,
+                system: TimerSystem,
+            }
+        }
+
+        fn tick(&mut self, dt: f32) {
+            self.system.update(self.context.clone(), dt).unwrap();
+        }
+
+        fn timers<R>(&self, f: impl FnOnce(&mut Timers) -> R) -> R {
+            let context = self.context.borrow();
+            let world = context.world.borrow();
+            f(&mut world.resource_mut::<Timers>())
+        }
+
+        fn spawn(&self) -> EntityHandle {
+            self.context.borrow().world.borrow_mut().spawn_entity()
+        }
+    }
+
+    fn counter() -> (Rc<Cell<u32>>, impl FnMut(Rc<RefCell<AppContext>>) + 'static) {
+        let count = Rc::new(Cell::new(0));
+        let inner = count.clone();
+        (count, move |_| inner.set(inner.get() + 1))
+    }
+
+    #[test]
+    fn after_fires_once() {
+        let mut harness = Harness::new();
+        let (count, f) = counter();
+        let handle = harness.timers(|timers| timers.after(1.0, f));
+
+        harness.tick(0.5);
+        assert_eq!(count.get(), 0);
+        harness.tick(0.6);
+        assert_eq!(count.get(), 1);
+        assert!(!harness.timers(|timers| timers.contains(handle)));
+
+        harness.tick(5.0);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn every_repeats_and_catches_up() {
+        let mut harness = Harness::new();
+        let (count, f) = counter();
+        let handle = harness.timers(|timers| timers.every(1.0, f));
+
+        for _ in 0..5 {
+            harness.tick(0.5);
+        }
+        assert_eq!(count.get(), 2);
+
+        // 一帧跨过多个间隔时补齐所有触发
+        harness.tick(3.0);
+        assert_eq!(count.get(), 5);
+        assert!(harness.timers(|timers| timers.contains(handle)));
+    }
+
+    #[test]
+    fn cancelled_timers_do_not_fire() {
+        let mut harness = Harness::new();
+        let (count, f) = counter();
+        let handle = harness.timers(|timers| timers.every(1.0, f));
+
+        harness.tick(1.0);
+        assert_eq!(count.get(), 1);
+        assert!(harness.timers(|timers| timers.cancel(handle)));
+        assert!(!harness.timers(|timers| timers.cancel(handle)));
+
+        harness.tick(5.0);
+        assert_eq!(count.get(), 1);
+        assert!(harness.timers(|timers| timers.is_empty()));
+    }
+
+    #[test]
+    fn timer_can_cancel_itself_from_callback() {
+        let mut harness = Harness::new();
+        let count = Rc::new(Cell::new(0));
+        let handle = Rc::new(Cell::new(None));
+        let (inner_count, inner_handle) = (count.clone(), handle.clone());
+        handle.set(Some(harness.timers(|timers| {
+            timers.every(1.0, move |ctx| {
+                inner_count.set(inner_count.get() + 1);
+                let context = ctx.borrow();
+                let world = context.world.borrow();
+                world
+                    .resource_mut::<Timers>()
+                    .cancel(inner_handle.get().unwrap());
+            })
+        })));
+
+        harness.tick(1.0);
+        harness.tick(1.0);
+        assert_eq!(count.get(), 1);
+        assert!(harness.timers(|timers| timers.is_empty()));
+    }
+
+    #[test]
+    fn entity_timers_are_cancelled_on_despawn() {
+        let mut harness = Harness::new();
+        let entity = harness.spawn();
+        let (count, f) = counter();
+        let handle = harness.timers(|timers| timers.every_for(entity, 1.0, f));
+
+        harness.tick(1.0);
+        assert_eq!(count.get(), 1);
+
+        harness
+            .context
+            .borrow()
+            .world
+            .borrow_mut()
+            .despawn_entity(entity);
+        harness.tick(5.0);
+        assert_eq!(count.get(), 1);
+        assert!(!harness.timers(|timers| timers.contains(handle)));
+    }
+
+    #[test]
+    fn cancel_for_only_cancels_the_entity() {
+        let mut harness = Harness::new();
+        let (entity, other) = (harness.spawn(), harness.spawn());
+        let (count, f) = counter();
+        let (other_count, g) = counter();
+        harness.timers(|timers| {
+            timers.after_for(entity, 1.0, f);
+            timers.after_for(other, 1.0, g);
+            timers.cancel_for(entity);
+        });
+
+        harness.tick(1.0);
+        assert_eq!(count.get(), 0);
+        assert_eq!(other_count.get(), 1);
+    }
+
+    #[test]
+    fn entity_timers_pause_while_inactive() {
+        let mut harness = Harness::new();
+        let parent = harness.spawn();
+        let entity = harness.spawn();
+        {
+            let context = harness.context.borrow();
+            let world = context.world.borrow();
+            world.set_parent(entity, parent).unwrap();
+        }
+        let (count, f) = counter();
+        harness.timers(|timers| timers.after_for(entity, 1.0, f));
+
+        harness.tick(0.5);
+        // 停用父实体时子实体的定时器同样暂停
+        harness
+            .context
+            .borrow()
+            .world
+            .borrow()
+            .set_active(parent, false);
+        harness.tick(5.0);
+        assert_eq!(count.get(), 0);
+
+        harness
+            .context
+            .borrow()
+            .world
+            .borrow()
+            .set_active(parent, true);
+        harness.tick(0.4);
+        assert_eq!(count.get(), 0);
+        harness.tick(0.2);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn sequence_runs_steps_in_order() {
+        let mut harness = Harness::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let (a, b, c) = (log.clone(), log.clone(), log.clone());
+        let sequence = Sequence::new()
+            .then(move |_| a.borrow_mut().push("start"))
+            .wait(1.0)
+            .over(1.0, move |_, t| {
+                b.borrow_mut().push(if t < 1.0 { "over" } else { "done" })
+            })
+            .then(move |_| c.borrow_mut().push("end"));
+        let handle = harness.timers(|timers| timers.sequence(sequence));
+
+        harness.tick(0.5);
+        assert_eq!(*log.borrow(), ["start"]);
+        // 等待结束后剩余的时间交给下一步
+        harness.tick(1.0);
+        assert_eq!(*log.borrow(), ["start", "over"]);
+        harness.tick(1.0);
+        assert_eq!(*log.borrow(), ["start", "over", "done", "end"]);
+        assert!(!harness.timers(|timers| timers.contains(handle)));
+    }
+}
@@ -1,12 +1,116 @@
 use crate::Resolution;
 
+/// 窗口显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// 无边框窗口，铺满显示器
+    Borderless(MonitorSelection),
+    /// 独占全屏
+    Fullscreen(MonitorSelection, VideoModeSelection),
+}
+
+/// 选择显示器，找不到时使用主显示器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonitorSelection {
+    #[default]
+    Primary,
+    /// `WindowState::monitors` 中的下标
+    Index(usize),
+    /// 窗口中心所在的显示器
+    Current,
+}
+
+/// 选择全屏时的视频模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VideoModeSelection {
+    /// 显示器当前的视频模式
+    #[default]
+    Current,
+    /// 指定的视频模式，找不到时使用最接近的
+    Specific(VideoMode),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+/// 显示器信息，在创建窗口时读取
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: String,
+    /// 显示器在虚拟桌面中的位置
+    pub position: (i32, i32),
+    pub current_mode: Option<VideoMode>,
+    pub video_modes: Vec<VideoMode>,
+}
+
+/// 光标模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode {
+    Normal,
+    /// 光标在窗口内隐藏，可以移出窗口
+    Hidden,
+    /// 隐藏并锁定光标，只报告移动量，用于第一人称操作
+    #[default]
+    Captured,
+}
+
+/// 对窗口的修改，在每帧结束时应用
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum WindowCommand {
+    SetMode(WindowMode),
+    SetCursorMode(CursorMode),
+    SetVSync(bool),
+    SetTitle(String),
+    SetSize(u32, u32),
+    SetPosition(i32, i32),
+    Minimize,
+    Maximize,
+    Restore,
+}
+
+/// 窗口状态和控制接口
+///
+/// 读取的是最近一次应用后的状态；修改会在本帧结束时应用到窗口上，
+/// 没有窗口时修改只会记录状态。
 pub struct WindowState {
+    // framebuffer 大小
     resolution: Resolution,
+    mode: WindowMode,
+    cursor_mode: CursorMode,
+    v_sync: bool,
+    title: String,
+    // 窗口大小和位置，单位为屏幕坐标
+    size: (u32, u32),
+    position: (i32, i32),
+    focused: bool,
+    minimized: bool,
+    maximized: bool,
+    monitors: Vec<MonitorInfo>,
+    commands: Vec<WindowCommand>,
 }
 
 impl WindowState {
     pub fn new(resolution: Resolution) -> Self {
-        Self { resolution }
+        Self {
+            resolution,
+            mode: WindowMode::Windowed,
+            cursor_mode: CursorMode::Normal,
+            v_sync: true,
+            title: String::new(),
+            size: (resolution.width, resolution.height),
+            position: (0, 0),
+            focused: true,
+            minimized: false,
+            maximized: false,
+            monitors: Vec::new(),
+            commands: Vec::new(),
+        }
     }
 
     pub fn set_resolution(&mut self, resolution: Resolution) {
@@ -16,4 +120,130 @@ impl WindowState {
     pub fn get_resolution(&self) -> Resolution {
         self.resolution
     }
+
+    pub fn mode(&self) -> WindowMode {
+        self.mode
+    }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    pub fn v_sync(&self) -> bool {
+        self.v_sync
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// 窗口大小，单位为屏幕坐标，高 DPI 下可能和 framebuffer 大小不同
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        self.position
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.maximized
+    }
+
+    pub fn monitors(&self) -> &[MonitorInfo] {
+        &self.monitors
+    }
+
+    /// 切换窗口、无边框和全屏
+    pub fn set_mode(&mut self, mode: WindowMode) {
+        self.commands.push(WindowCommand::SetMode(mode));
+    }
+
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) {
+        self.commands
+            .push(WindowCommand::SetCursorMode(cursor_mode));
+    }
+
+    pub fn set_v_sync(&mut self, v_sync: bool) {
+        self.commands.push(WindowCommand::SetVSync(v_sync));
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.commands.push(WindowCommand::SetTitle(title.into()));
+    }
+
+    /// 设置窗口大小，全屏时无效
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.commands.push(WindowCommand::SetSize(width, height));
+    }
+
+    /// 设置窗口位置，全屏时无效
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        self.commands.push(WindowCommand::SetPosition(x, y));
+    }
+
+    pub fn minimize(&mut self) {
+        self.commands.push(WindowCommand::Minimize);
+    }
+
+    pub fn maximize(&mut self) {
+        self.commands.push(WindowCommand::Maximize);
+    }
+
+    /// 从最小化或最大化恢复
+    pub fn restore(&mut self) {
+        self.commands.push(WindowCommand::Restore);
+    }
+
+    pub(crate) fn take_commands(&mut self) -> Vec<WindowCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    pub(crate) fn set_mode_state(&mut self, mode: WindowMode) {
+        self.mode = mode;
+    }
+
+    pub(crate) fn set_cursor_mode_state(&mut self, cursor_mode: CursorMode) {
+        self.cursor_mode = cursor_mode;
+    }
+
+    pub(crate) fn set_v_sync_state(&mut self, v_sync: bool) {
+        self.v_sync = v_sync;
+    }
+
+    pub(crate) fn set_title_state(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub(crate) fn set_size_state(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+    }
+
+    pub(crate) fn set_position_state(&mut self, x: i32, y: i32) {
+        self.position = (x, y);
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub(crate) fn set_minimized(&mut self, minimized: bool) {
+        self.minimized = minimized;
+    }
+
+    pub(crate) fn set_maximized(&mut self, maximized: bool) {
+        self.maximized = maximized;
+    }
+
+    pub(crate) fn set_monitors(&mut self, monitors: Vec<MonitorInfo>) {
+        self.monitors = monitors;
+    }
 }
@@ -1,3 +1,6 @@
+// 让 derive 宏生成的 `::glotus::` 路径在本 crate 内也能解析
+extern crate self as glotus;
+
 mod app;
 mod base;
 mod context;
@@ -6,3 +9,4 @@ mod utils;
 pub use app::*;
 pub use base::*;
 pub use context::*;
+pub use glotus_derive::Bundle;
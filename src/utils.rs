@@ -1,3 +1,3 @@
 mod log_builder;
 
-pub(crate) use log_builder::setup_logger;
\ No newline at end of file
+pub(crate) use log_builder::setup_logger;
@@ -1,34 +1,422 @@
-use chrono::Local;
-use env_logger::{Builder, Target};
-use log::LevelFilter;
+use chrono::{Local, NaiveDateTime};
+use log::{Log, Metadata, Record, warn};
 use std::{
     env,
-    fs::{self, File}, path::Path,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, RwLock},
 };
 
-pub(crate) fn setup_logger() {
-    let log_dir = "logs";
+use crate::{FileLogConfig, LogBuffer, LogConfig, LogEntry, LogSink};
 
-    let args: Vec<String> = env::args().collect();
-    let exe_name = args
-        .get(0)
-        .and_then(|path_str| {
-            let path = Path::new(path_str);
-            path.file_stem()
+const FILE_TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+static LOGGER: Logger = Logger {
+    state: RwLock::new(None),
+};
+// 第一次安装时是否成功，失败说明宿主程序已经安装了其他 logger
+static OWNS_LOGGER: OnceLock<bool> = OnceLock::new();
+
+/// 按配置安装全局 logger
+///
+/// log crate 只允许安装一次，再次调用时替换已安装 logger 的配置，
+/// 因此同一进程中可以创建多个 App。
+pub(crate) fn setup_logger(config: &LogConfig) {
+    if !config.install {
+        return;
+    }
+    let owns_logger = *OWNS_LOGGER.get_or_init(|| log::set_logger(&LOGGER).is_ok());
+    if !owns_logger {
+        warn!("another logger is already installed, log config is ignored");
+        return;
+    }
+
+    let mut errors = Vec::new();
+    let mut sinks: Vec<Sink> = config
+        .sinks
+        .iter()
+        .filter_map(|sink| Sink::open(sink).map_err(|e| errors.push(e)).ok())
+        .collect();
+    // 日志文件都无法创建时至少保留错误输出
+    if sinks.is_empty() && !errors.is_empty() {
+        sinks.push(Sink::Stderr);
+    }
+
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(config.level);
+    for (module, level) in &config.module_levels {
+        builder.filter_module(module, *level);
+    }
+    let filter = builder.build();
+
+    log::set_max_level(filter.filter());
+    *LOGGER
+        .state
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(LoggerState { filter, sinks });
+
+    for error in errors {
+        warn!("failed to open log file: {}", error);
+    }
+}
+
+struct Logger {
+    state: RwLock<Option<LoggerState>>,
+}
+
+struct LoggerState {
+    // 只使用 env_logger 的模块过滤
+    filter: env_logger::Logger,
+    sinks: Vec<Sink>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let state = self
+            .state
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state
+            .as_ref()
+            .is_some_and(|state| state.filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        let state = self
+            .state
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(state) = state.as_ref() else {
+            return;
+        };
+        if !state.filter.matches(record) {
+            return;
+        }
+
+        let entry = LogEntry {
+            time: Local::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let line = format!(
+            "[{} {:<5} {}] {}\n",
+            entry.time.format("%Y-%m-%dT%H:%M:%S%.3f"),
+            entry.level,
+            entry.target,
+            entry.message
+        );
+        for sink in &state.sinks {
+            sink.write(&line, &entry);
+        }
+    }
+
+    fn flush(&self) {
+        let state = self
+            .state
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(state) = state.as_ref() {
+            for sink in &state.sinks {
+                sink.flush();
+            }
+        }
+    }
+}
+
+enum Sink {
+    Stdout,
+    Stderr,
+    File(Mutex<RotatingFile>),
+    Memory(LogBuffer),
+}
+
+impl Sink {
+    fn open(sink: &LogSink) -> io::Result<Self> {
+        Ok(match sink {
+            LogSink::Stdout => Sink::Stdout,
+            LogSink::Stderr => Sink::Stderr,
+            LogSink::File(config) => Sink::File(Mutex::new(RotatingFile::open(config)?)),
+            LogSink::Memory(buffer) => Sink::Memory(buffer.clone()),
         })
-        .and_then(|os_str| os_str.to_str())
-        .unwrap_or("unknown_example");
-    fs::create_dir_all(log_dir).expect("Failed to create logs directory");
+    }
+
+    // 写日志失败时无法再通过日志报告，标准输出和错误输出的失败直接忽略
+    fn write(&self, line: &str, entry: &LogEntry) {
+        match self {
+            Sink::Stdout => {
+                let _ = io::stdout().lock().write_all(line.as_bytes());
+            }
+            Sink::Stderr => {
+                let _ = io::stderr().lock().write_all(line.as_bytes());
+            }
+            Sink::File(file) => {
+                let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                match file.write(line.as_bytes()) {
+                    Ok(()) => file.write_failed = false,
+                    // 连续失败只报告第一次，写入恢复后再次失败时重新报告
+                    Err(e) if !file.write_failed => {
+                        file.write_failed = true;
+                        let message = format!("failed to write log file {:?}: {}\n", file.path, e);
+                        let _ = io::stderr().lock().write_all(message.as_bytes());
+                    }
+                    Err(_) => {}
+                }
+            }
+            Sink::Memory(buffer) => buffer.push(entry.clone()),
+        }
+    }
+
+    fn flush(&self) {
+        match self {
+            Sink::Stdout => {
+                let _ = io::stdout().flush();
+            }
+            Sink::Stderr => {
+                let _ = io::stderr().flush();
+            }
+            Sink::File(file) => {
+                let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let _ = file.file.flush();
+            }
+            Sink::Memory(_) => {}
+        }
+    }
+}
+
+/// 超过大小后轮换到新文件，并删除超出保留数量的旧文件
+struct RotatingFile {
+    config: FileLogConfig,
+    name: String,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    // 下一个文件的序号，删除旧文件后也不会重用文件名
+    next_index: u32,
+    // 上一次写入是否失败，用于只报告一次连续的失败
+    write_failed: bool,
+}
+
+impl RotatingFile {
+    fn open(config: &FileLogConfig) -> io::Result<Self> {
+        let name = config.name.clone().unwrap_or_else(exe_name);
+        fs::create_dir_all(&config.directory)?;
+        let mut next_index = 0;
+        let (path, file) = create_log_file(&config.directory, &name, &mut next_index)?;
+        let rotating_file = Self {
+            config: config.clone(),
+            name,
+            path,
+            file,
+            size: 0,
+            next_index,
+            write_failed: false,
+        };
+        rotating_file.remove_old_files();
+        Ok(rotating_file)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if let Some(max_file_size) = self.config.max_file_size
+            && self.size > 0
+            && self.size + bytes.len() as u64 > max_file_size
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.size += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let (path, file) =
+            create_log_file(&self.config.directory, &self.name, &mut self.next_index)?;
+        self.path = path;
+        self.file = file;
+        self.size = 0;
+        self.remove_old_files();
+        Ok(())
+    }
+
+    fn remove_old_files(&self) {
+        let Some(max_files) = self.config.max_files else {
+            return;
+        };
+        let Ok(dir) = fs::read_dir(&self.config.directory) else {
+            return;
+        };
+        let mut files: Vec<_> = dir
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                let (timestamp, index) = parse_log_file_name(file_name.to_str()?, &self.name)?;
+                Some(((timestamp.to_string(), index), entry.path()))
+            })
+            .collect();
+        if files.len() <= max_files.max(1) {
+            return;
+        }
+        files.sort();
+        let excess = files.len() - max_files.max(1);
+        for (_, path) in files.into_iter().take(excess) {
+            if path != self.path {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// 创建 `{时间}_{name}.log`，序号不为 0 时为 `{时间}_{name}_{序号}.log`
+fn create_log_file(directory: &Path, name: &str, index: &mut u32) -> io::Result<(PathBuf, File)> {
+    let timestamp = Local::now().format(FILE_TIMESTAMP_FORMAT);
+    loop {
+        let file_name = match *index {
+            0 => format!("{}_{}.log", timestamp, name),
+            _ => format!("{}_{}_{}.log", timestamp, name, index),
+        };
+        let path = directory.join(file_name);
+        let result = File::options().write(true).create_new(true).open(&path);
+        *index += 1;
+        match result {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 解析 `create_log_file` 创建的同名日志，返回用于排序的时间和序号
+fn parse_log_file_name<'a>(file_name: &'a str, name: &str) -> Option<(&'a str, u32)> {
+    let stem = file_name.strip_suffix(".log")?;
+    // 时间部分固定为 19 个字符
+    let (timestamp, rest) = stem.split_at_checked(19)?;
+    NaiveDateTime::parse_from_str(timestamp, FILE_TIMESTAMP_FORMAT).ok()?;
+    let rest = rest.strip_prefix('_')?.strip_prefix(name)?;
+    if rest.is_empty() {
+        return Some((timestamp, 0));
+    }
+    let index = rest.strip_prefix('_')?;
+    if !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((timestamp, index.parse().ok()?))
+}
+
+fn exe_name() -> String {
+    env::args()
+        .next()
+        .and_then(|path| {
+            Path::new(&path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| String::from("unknown_example"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个测试使用单独的临时目录
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("glotus_log_{}_{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_config(
+        directory: &Path,
+        max_file_size: u64,
+        max_files: Option<usize>,
+    ) -> FileLogConfig {
+        FileLogConfig {
+            directory: directory.to_path_buf(),
+            name: Some(String::from("app")),
+            max_file_size: Some(max_file_size),
+            max_files,
+        }
+    }
+
+    fn file_names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn parses_only_own_log_files() {
+        let ts = "2026-01-02_03-04-05";
+        assert_eq!(
+            parse_log_file_name(&format!("{ts}_app.log"), "app"),
+            Some((ts, 0))
+        );
+        assert_eq!(
+            parse_log_file_name(&format!("{ts}_app_12.log"), "app"),
+            Some((ts, 12))
+        );
+
+        for file_name in [
+            format!("{ts}_app_x.log"),
+            format!("{ts}_app_.log"),
+            format!("{ts}_other.log"),
+            format!("{ts}_application.log"),
+            format!("{ts}_app.txt"),
+            String::from("2026-13-02_03-04-05_app.log"),
+            String::from("app.log"),
+        ] {
+            assert_eq!(parse_log_file_name(&file_name, "app"), None, "{file_name}");
+        }
+    }
+
+    #[test]
+    fn rotates_when_file_would_exceed_max_size() {
+        let dir = temp_dir("rotate");
+        let mut file = RotatingFile::open(&file_config(&dir, 10, None)).unwrap();
+        for _ in 0..3 {
+            file.write(b"12345\n").unwrap();
+        }
+        file.file.flush().unwrap();
+
+        let names = file_names(&dir);
+        assert_eq!(names.len(), 3);
+        for name in &names {
+            assert!(parse_log_file_name(name, "app").is_some(), "{name}");
+            assert_eq!(fs::read_to_string(dir.join(name)).unwrap(), "12345\n");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let file_name = format!("{}/{}_{}.log", log_dir, timestamp, exe_name);
+    #[test]
+    fn keeps_newest_files_and_ignores_other_names() {
+        let dir = temp_dir("retention");
+        let old = "2000-01-01_00-00-00";
+        for name in ["app", "app_x", "other"] {
+            fs::write(dir.join(format!("{old}_{name}.log")), "").unwrap();
+        }
 
-    let log_file =
-        File::create(&file_name).expect(&format!("Failed to create log file: {}", file_name));
+        let mut file = RotatingFile::open(&file_config(&dir, 1, Some(2))).unwrap();
+        for line in ["a\n", "b\n", "c\n", "d\n"] {
+            file.write(line.as_bytes()).unwrap();
+        }
+        file.file.flush().unwrap();
 
-    Builder::new()
-        .target(Target::Pipe(Box::new(log_file))) // 输出到文件
-        .filter_level(LevelFilter::Info) // 设置默认日志级别
-        .format_timestamp_secs() // 添加时间戳
-        .init(); // 初始化全局 logger
+        let names = file_names(&dir);
+        let own: Vec<&String> = names
+            .iter()
+            .filter(|name| parse_log_file_name(name, "app").is_some())
+            .collect();
+        // 旧的同名日志被删除，只保留最新的两个
+        assert_eq!(own.len(), 2);
+        assert!(own.iter().all(|name| !name.starts_with(old)));
+        assert_eq!(fs::read_to_string(&file.path).unwrap(), "d\n");
+        assert!(names.contains(&format!("{old}_app_x.log")));
+        assert!(names.contains(&format!("{old}_other.log")));
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
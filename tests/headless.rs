@@ -0,0 +1,474 @@
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::rc::Rc;
+
+use glotus::{
+    App, AppConfig, AppContext, Camera, CameraPlugin, CursorMode, EntityHandle, FixedStepOverrun,
+    FixedTime, GlobalTransform, HeadlessMode, ISystem, LogConfig, ManualClock, MonitorSelection,
+    Profiler, Resolution, Time, Transform, WindowMode, WindowState,
+};
+
+/// 统计 update 和 fixed_update 的运行次数
+#[derive(Default)]
+struct Counts {
+    update: Cell<u32>,
+    fixed_update: Cell<u32>,
+}
+
+struct CountSystem(Rc<Counts>);
+
+impl ISystem for CountSystem {
+    fn name(&self) -> &str {
+        "CountSystem"
+    }
+
+    fn update(&mut self, _ctx: Rc<RefCell<AppContext>>, _dt: f32) -> Result<(), Box<dyn Error>> {
+        self.0.update.set(self.0.update.get() + 1);
+        Ok(())
+    }
+
+    fn fixed_update(
+        &mut self,
+        _ctx: Rc<RefCell<AppContext>>,
+        _dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        self.0.fixed_update.set(self.0.fixed_update.get() + 1);
+        Ok(())
+    }
+}
+
+fn headless_app() -> (Rc<RefCell<App>>, Rc<Counts>) {
+    let app = App::new_with_config(AppConfig {
+        headless: HeadlessMode::NoGl,
+        // 测试不写日志文件
+        log: LogConfig {
+            install: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let counts = Rc::new(Counts::default());
+    app.borrow().add_system(CountSystem(counts.clone()));
+    (app, counts)
+}
+
+fn with_time(app: &Rc<RefCell<App>>, f: impl Fn(&mut Time)) {
+    app.borrow()
+        .build(|ctx| {
+            f(&mut ctx.borrow().world.borrow().resource_mut::<Time>());
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn run_frames_runs_one_fixed_update_per_frame() {
+    let (app, counts) = headless_app();
+
+    app.borrow_mut().run_frames(10);
+
+    assert_eq!(counts.update.get(), 10);
+    assert_eq!(counts.fixed_update.get(), 10);
+}
+
+#[test]
+fn run_frames_follows_time_scale() {
+    let (app, counts) = headless_app();
+    with_time(&app, |time| time.set_time_scale(2.0));
+
+    app.borrow_mut().run_frames(10);
+
+    assert_eq!(counts.update.get(), 10);
+    assert_eq!(counts.fixed_update.get(), 20);
+}
+
+#[test]
+fn run_frames_skips_fixed_update_while_paused() {
+    let (app, counts) = headless_app();
+    with_time(&app, |time| time.pause());
+
+    app.borrow_mut().run_frames(5);
+    assert_eq!(counts.update.get(), 5);
+    assert_eq!(counts.fixed_update.get(), 0);
+
+    with_time(&app, |time| time.resume());
+    app.borrow_mut().run_frames(5);
+    assert_eq!(counts.update.get(), 10);
+    assert_eq!(counts.fixed_update.get(), 5);
+}
+
+#[test]
+fn profiler_uses_app_clock() {
+    let (app, _) = headless_app();
+    let clock = ManualClock::new();
+    app.borrow_mut().set_clock(clock.clone());
+    app.borrow()
+        .build(|ctx| {
+            ctx.borrow()
+                .world
+                .borrow()
+                .resource_mut::<Profiler>()
+                .set_enabled(true);
+            Ok(())
+        })
+        .unwrap();
+
+    clock.set(10.0);
+    app.borrow_mut().run_frames(1);
+    clock.advance(0.5);
+    app.borrow_mut().run_frames(1);
+
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            let profiler = world.resource::<Profiler>();
+            let starts: Vec<f64> = profiler.frames().map(|frame| frame.start).collect();
+            assert_eq!(starts, [10.0, 10.5]);
+            // 时钟在帧内没有推进
+            assert!(profiler.frames().all(|frame| frame.duration == 0.0));
+            Ok(())
+        })
+        .unwrap();
+}
+
+/// 在 Update 阶段记录实体的世界坐标
+struct ReadGlobalSystem(Rc<Cell<Option<glam::Vec3>>>);
+
+impl ISystem for ReadGlobalSystem {
+    fn name(&self) -> &str {
+        "ReadGlobalSystem"
+    }
+
+    fn update(&mut self, ctx: Rc<RefCell<AppContext>>, _dt: f32) -> Result<(), Box<dyn Error>> {
+        let context = ctx.borrow();
+        let world = context.world.borrow();
+        let query = world.query::<&GlobalTransform>()?;
+        self.0
+            .set(query.iter().next().map(|(_, global)| global.translation()));
+        Ok(())
+    }
+}
+
+#[test]
+fn update_systems_read_current_global_transform() {
+    let (app, _) = headless_app();
+    let seen = Rc::new(Cell::new(None));
+    app.borrow().add_system(ReadGlobalSystem(seen.clone()));
+    app.borrow()
+        .build(|ctx| {
+            ctx.borrow()
+                .world
+                .borrow_mut()
+                .spawn_entity_with(Transform::from_position(1.0, 2.0, 3.0));
+            Ok(())
+        })
+        .unwrap();
+
+    app.borrow_mut().run_frames(1);
+
+    assert_eq!(seen.get(), Some(glam::Vec3::new(1.0, 2.0, 3.0)));
+}
+
+fn camera_aspect(app: &Rc<RefCell<App>>, camera: EntityHandle) -> f32 {
+    let aspect = Cell::new(0.0_f32);
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            aspect.set(
+                world
+                    .get_manager::<Camera>()
+                    .get(camera)
+                    .unwrap()
+                    .aspect_ratio,
+            );
+            Ok(())
+        })
+        .unwrap();
+    aspect.get()
+}
+
+#[test]
+fn camera_spawned_inactive_is_set_up_when_activated() {
+    let app = App::new_with_config(AppConfig {
+        headless: HeadlessMode::NoGl,
+        resolution: Resolution::new(800, 800),
+        log: LogConfig {
+            install: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let camera = Cell::new(EntityHandle::default());
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let mut world = context.world.borrow_mut();
+            let entity = world.spawn_entity_with(Camera::new(true).with_aspect_ratio(2.0));
+            world.set_active(entity, false);
+            camera.set(entity);
+            Ok(())
+        })
+        .unwrap();
+
+    let camera = camera.get();
+    app.borrow_mut().run_frames(1);
+    assert_eq!(camera_aspect(&app, camera), 2.0);
+
+    app.borrow()
+        .build(|ctx| {
+            ctx.borrow().world.borrow().set_active(camera, true);
+            Ok(())
+        })
+        .unwrap();
+    app.borrow_mut().run_frames(1);
+    assert_eq!(camera_aspect(&app, camera), 1.0);
+}
+
+#[test]
+fn render_plugin_registers_camera_without_camera_plugin() {
+    let app = App::builder_with_config(AppConfig {
+        headless: HeadlessMode::NoGl,
+        log: LogConfig {
+            install: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .without_plugin::<CameraPlugin>()
+    .build();
+    let camera = Cell::new(EntityHandle::default());
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let entity = context
+                .world
+                .borrow_mut()
+                .spawn_entity_with(Camera::new(true).with_aspect_ratio(2.0));
+            camera.set(entity);
+            Ok(())
+        })
+        .unwrap();
+
+    app.borrow_mut().run_frames(1);
+
+    // 没有 CameraSystem，宽高比保持不变
+    assert_eq!(camera_aspect(&app, camera.get()), 2.0);
+}
+
+fn window_modes(app: &Rc<RefCell<App>>) -> (WindowMode, CursorMode) {
+    let modes = Cell::new((WindowMode::Windowed, CursorMode::Normal));
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            let window_state = world.resource::<WindowState>();
+            modes.set((window_state.mode(), window_state.cursor_mode()));
+            Ok(())
+        })
+        .unwrap();
+    modes.get()
+}
+
+#[test]
+fn window_state_changes_apply_without_window() {
+    let (app, _) = headless_app();
+    app.borrow_mut().run_frames(1);
+    let borderless = WindowMode::Borderless(MonitorSelection::Index(1));
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            let mut window_state = world.resource_mut::<WindowState>();
+            window_state.set_mode(borderless);
+            window_state.set_cursor_mode(CursorMode::Hidden);
+            Ok(())
+        })
+        .unwrap();
+
+    // 修改在下一帧开始时应用
+    assert_ne!(window_modes(&app), (borderless, CursorMode::Hidden));
+    app.borrow_mut().run_frames(1);
+    assert_eq!(window_modes(&app), (borderless, CursorMode::Hidden));
+}
+
+/// fixed_dt 为 0.25 秒，每帧最多两步，最多累积 2 秒
+fn fixed_step_app(overrun: FixedStepOverrun) -> (Rc<RefCell<App>>, Rc<Counts>) {
+    let app = App::new_with_config(AppConfig {
+        headless: HeadlessMode::NoGl,
+        fixed_update_fps: 4,
+        max_fixed_steps_per_frame: 2,
+        max_fixed_accumulated_time: 2.0,
+        fixed_step_overrun: overrun,
+        log: LogConfig {
+            install: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let counts = Rc::new(Counts::default());
+    app.borrow().add_system(CountSystem(counts.clone()));
+    (app, counts)
+}
+
+/// 上一帧的 (steps, dropped_time, alpha)
+fn fixed_frame(app: &Rc<RefCell<App>>) -> (u32, f64, f32) {
+    let frame = Cell::new((0, 0.0, 0.0));
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            let fixed_time = world.resource::<FixedTime>();
+            frame.set((
+                fixed_time.steps(),
+                fixed_time.dropped_time(),
+                fixed_time.alpha(),
+            ));
+            Ok(())
+        })
+        .unwrap();
+    frame.get()
+}
+
+#[test]
+fn drop_overrun_discards_whole_steps_beyond_the_cap() {
+    let (app, counts) = fixed_step_app(FixedStepOverrun::Drop);
+
+    // 4.5 步的时间只运行两步，丢弃两步，保留不足一步的部分用于插值
+    app.borrow_mut().step(1.125);
+    assert_eq!(fixed_frame(&app), (2, 0.5, 0.5));
+
+    app.borrow_mut().step(0.125);
+    assert_eq!(fixed_frame(&app), (1, 0.0, 0.0));
+    assert_eq!(counts.fixed_update.get(), 3);
+}
+
+#[test]
+fn slow_overrun_carries_remaining_steps_to_next_frames() {
+    let (app, counts) = fixed_step_app(FixedStepOverrun::Slow);
+
+    app.borrow_mut().step(1.125);
+    assert_eq!(fixed_frame(&app).0, 2);
+    assert_eq!(fixed_frame(&app).1, 0.0);
+
+    // 剩余的 2.5 步在后面的帧中补上
+    app.borrow_mut().step(0.0);
+    assert_eq!(fixed_frame(&app), (2, 0.0, 0.5));
+    app.borrow_mut().step(0.0);
+    assert_eq!(fixed_frame(&app), (0, 0.0, 0.5));
+    assert_eq!(counts.fixed_update.get(), 4);
+}
+
+#[test]
+fn accumulated_time_beyond_the_limit_is_always_dropped() {
+    let (app, counts) = fixed_step_app(FixedStepOverrun::Slow);
+
+    app.borrow_mut().step(3.0);
+    assert_eq!(fixed_frame(&app).0, 2);
+    assert_eq!(fixed_frame(&app).1, 1.0);
+
+    // 只补上限以内的 2 秒，共 8 步
+    for _ in 0..3 {
+        app.borrow_mut().step(0.0);
+    }
+    assert_eq!(fixed_frame(&app), (2, 0.0, 0.0));
+    app.borrow_mut().step(0.0);
+    assert_eq!(fixed_frame(&app), (0, 0.0, 0.0));
+    assert_eq!(counts.fixed_update.get(), 8);
+}
+
+/// 每个固定步把实体沿 x 轴移动 1，请求瞬移时在 update 中直接移动到 x = 10
+struct Mover {
+    entity: EntityHandle,
+    x: f32,
+    teleport: Rc<Cell<bool>>,
+}
+
+impl Mover {
+    fn set_x(&mut self, ctx: &Rc<RefCell<AppContext>>, x: f32) {
+        self.x = x;
+        let context = ctx.borrow();
+        let world = context.world.borrow();
+        let mut transforms = world.get_manager_mut::<Transform>();
+        *transforms.get_mut(self.entity).unwrap() = Transform::from_position(x, 0.0, 0.0);
+    }
+}
+
+impl ISystem for Mover {
+    fn name(&self) -> &str {
+        "Mover"
+    }
+
+    fn update(&mut self, ctx: Rc<RefCell<AppContext>>, _dt: f32) -> Result<(), Box<dyn Error>> {
+        if self.teleport.replace(false) {
+            self.set_x(&ctx, 10.0);
+        }
+        Ok(())
+    }
+
+    fn fixed_update(
+        &mut self,
+        ctx: Rc<RefCell<AppContext>>,
+        _dt: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        self.set_x(&ctx, self.x + 1.0);
+        Ok(())
+    }
+}
+
+/// 实体的 (世界坐标 x, 渲染坐标 x)
+fn global_x(app: &Rc<RefCell<App>>, entity: EntityHandle) -> (f32, f32) {
+    let x = Cell::new((0.0, 0.0));
+    app.borrow()
+        .build(|ctx| {
+            let context = ctx.borrow();
+            let world = context.world.borrow();
+            let globals = world.get_manager::<GlobalTransform>();
+            let global = globals.get(entity).unwrap();
+            x.set((global.translation().x, global.render_matrix().w_axis.x));
+            Ok(())
+        })
+        .unwrap();
+    x.get()
+}
+
+#[test]
+fn render_matrix_interpolates_fixed_steps_and_snaps_on_teleport() {
+    let (app, _) = fixed_step_app(FixedStepOverrun::Slow);
+    let entity = Cell::new(EntityHandle::default());
+    app.borrow()
+        .build(|ctx| {
+            let spawned = ctx
+                .borrow()
+                .world
+                .borrow_mut()
+                .spawn_entity_with(Transform::from_position(0.0, 0.0, 0.0));
+            entity.set(spawned);
+            Ok(())
+        })
+        .unwrap();
+    let entity = entity.get();
+    let teleport = Rc::new(Cell::new(false));
+    app.borrow().add_system(Mover {
+        entity,
+        x: 0.0,
+        teleport: teleport.clone(),
+    });
+
+    app.borrow_mut().step(0.25);
+    assert_eq!(global_x(&app, entity), (1.0, 1.0));
+
+    // 1.5 步：运行一步后 alpha 为 0.5，渲染位置在 x = 1 和 x = 2 之间
+    app.borrow_mut().step(0.375);
+    assert_eq!(global_x(&app, entity), (2.0, 1.5));
+
+    // update 中的瞬移不做插值
+    teleport.set(true);
+    app.borrow_mut().step(0.0);
+    assert_eq!(global_x(&app, entity), (10.0, 10.0));
+
+    // 下一个固定步从瞬移后的位置开始插值
+    app.borrow_mut().step(0.25);
+    assert_eq!(global_x(&app, entity), (11.0, 10.5));
+}